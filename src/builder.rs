@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{Client, Proxy};
+
+use crate::error::{NovaError, Result, ValidationError};
+use crate::{NovaPoshta, RateLimiter, Retry, RetryPolicy};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.novaposhta.ua/v2.0/json/";
+
+pub const ENV_API_KEY: &str = "NOVAPOSHTA_KEY";
+pub const ENV_BASE_URL: &str = "NOVAPOSHTA_BASE_URL";
+/// Request timeout in seconds.
+pub const ENV_TIMEOUT: &str = "NOVAPOSHTA_TIMEOUT";
+/// `UA` or `RU`.
+pub const ENV_LANGUAGE: &str = "NOVAPOSHTA_LANGUAGE";
+
+/// Language of descriptions in responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NovaLanguage {
+    Ua,
+    Ru,
+}
+
+impl NovaLanguage {
+    pub fn code(&self) -> &'static str {
+        match self {
+            NovaLanguage::Ua => "UA",
+            NovaLanguage::Ru => "RU",
+        }
+    }
+}
+
+impl std::str::FromStr for NovaLanguage {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "UA" | "UK" => Ok(NovaLanguage::Ua),
+            "RU" => Ok(NovaLanguage::Ru),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Configures a [`NovaPoshta`] client.
+///
+/// Headers and the request timeout are applied to every request, so they also work
+/// with a client passed through [`NovaPoshtaBuilder::client`]. Proxy and connect
+/// timeout can only be set on a client built here and are ignored otherwise.
+#[derive(Debug)]
+pub struct NovaPoshtaBuilder {
+    api_key: String,
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    headers: HeaderMap,
+    language: Option<NovaLanguage>,
+    retry_policy: RetryPolicy,
+    limiter: Option<Arc<RateLimiter>>,
+    client: Option<Client>,
+}
+
+impl NovaPoshtaBuilder {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        NovaPoshtaBuilder {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            headers: HeaderMap::new(),
+            language: None,
+            retry_policy: RetryPolicy::default(),
+            limiter: None,
+            client: None,
+        }
+    }
+
+    /// Reads the api key and optional settings from `NOVAPOSHTA_*` variables.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let api_key = var(ENV_API_KEY)
+            .filter(|key| !key.trim().is_empty())
+            .ok_or(NovaError::MissingEnv(ENV_API_KEY))?;
+        let mut builder = NovaPoshtaBuilder::new(api_key);
+        if let Some(base_url) = var(ENV_BASE_URL) {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(value) = var(ENV_TIMEOUT) {
+            let seconds: u64 = value.trim().parse().map_err(|_| NovaError::InvalidEnv {
+                name: ENV_TIMEOUT,
+                value: value.clone(),
+            })?;
+            builder = builder.timeout(Duration::from_secs(seconds));
+        }
+        if let Some(value) = var(ENV_LANGUAGE) {
+            let language = value.parse().map_err(|_| NovaError::InvalidEnv {
+                name: ENV_LANGUAGE,
+                value: value.clone(),
+            })?;
+            builder = builder.language(language);
+        }
+        Ok(builder)
+    }
+
+    /// Endpoint every request is posted to, useful to point at a stub server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sent as `Language` with every request.
+    pub fn language(mut self, language: NovaLanguage) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Backoff for failed requests, see [`Retry`] for what is retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Throttles requests on this client; pass the same `Arc` to every client sharing
+    /// an api key.
+    pub fn rate_limiter(mut self, limiter: impl Into<Arc<RateLimiter>>) -> Self {
+        self.limiter = Some(limiter.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn user_agent(self, user_agent: &str) -> Result<Self> {
+        self.header(USER_AGENT, user_agent)
+    }
+
+    pub fn header(mut self, name: HeaderName, value: &str) -> Result<Self> {
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| ValidationError::new(name.as_str(), e.to_string()))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Reuse an existing client, e.g. to share its connection pool.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn build(self) -> Result<NovaPoshta> {
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = Client::builder();
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+        Ok(NovaPoshta {
+            api_key: self.api_key,
+            client,
+            base_url: self.base_url,
+            timeout: self.timeout,
+            headers: self.headers,
+            language: self.language,
+            retry_policy: self.retry_policy,
+            retry: Retry::Auto,
+            limiter: self.limiter,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| map.get(name).cloned()
+    }
+
+    #[test]
+    fn missing_key_is_an_error() {
+        let err = NovaPoshtaBuilder::from_vars(vars(&[])).unwrap_err();
+        assert!(matches!(err, NovaError::MissingEnv(ENV_API_KEY)));
+
+        let err = NovaPoshtaBuilder::from_vars(vars(&[(ENV_API_KEY, "  ")])).unwrap_err();
+        assert!(matches!(err, NovaError::MissingEnv(ENV_API_KEY)));
+    }
+
+    #[test]
+    fn only_key_uses_defaults() {
+        let builder = NovaPoshtaBuilder::from_vars(vars(&[(ENV_API_KEY, "key")])).unwrap();
+        assert_eq!(builder.api_key, "key");
+        assert_eq!(builder.base_url, DEFAULT_BASE_URL);
+        assert_eq!(builder.timeout, None);
+        assert_eq!(builder.language, None);
+    }
+
+    #[test]
+    fn optional_vars_are_read() {
+        let builder = NovaPoshtaBuilder::from_vars(vars(&[
+            (ENV_API_KEY, "key"),
+            (ENV_BASE_URL, "http://127.0.0.1:8080/"),
+            (ENV_TIMEOUT, "15"),
+            (ENV_LANGUAGE, "ru"),
+        ]))
+        .unwrap();
+        assert_eq!(builder.base_url, "http://127.0.0.1:8080/");
+        assert_eq!(builder.timeout, Some(Duration::from_secs(15)));
+        assert_eq!(builder.language, Some(NovaLanguage::Ru));
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let err =
+            NovaPoshtaBuilder::from_vars(vars(&[(ENV_API_KEY, "key"), (ENV_TIMEOUT, "soon")]))
+                .unwrap_err();
+        assert!(
+            matches!(err, NovaError::InvalidEnv { name: ENV_TIMEOUT, value } if value == "soon")
+        );
+
+        let err = NovaPoshtaBuilder::from_vars(vars(&[(ENV_API_KEY, "key"), (ENV_LANGUAGE, "en")]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NovaError::InvalidEnv {
+                name: ENV_LANGUAGE,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! Deserializers for the loosely typed values the api sends back.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+fn value_to_string(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// Messages come either as an array or as an object keyed by code.
+pub(crate) fn messages<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(items) => items.into_iter().map(value_to_string).collect(),
+        Value::Object(map) => map.into_iter().map(|(_, v)| value_to_string(v)).collect(),
+        Value::Null => vec![],
+        other => vec![value_to_string(other)],
+    })
+}
+
+/// Codes come as numbers or as numeric strings.
+pub(crate) fn codes<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let items = match Value::deserialize(deserializer)? {
+        Value::Array(items) => items,
+        Value::Object(map) => map.into_iter().map(|(_, v)| v).collect(),
+        _ => vec![],
+    };
+    Ok(items
+        .into_iter()
+        .filter_map(|v| match v {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Flags come as `"1"`/`"0"`, sometimes as numbers or booleans.
+pub(crate) fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(b) => b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !matches!(s.trim(), "" | "0" | "false"),
+        _ => false,
+    })
+}
+
+/// Numbers come either as json numbers or as strings, empty strings mean the default.
+pub(crate) fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr + Default,
+{
+    let text = match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s,
+        _ => return Ok(T::default()),
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(T::default());
+    }
+    text.parse()
+        .map_err(|_| serde::de::Error::custom(format!("invalid number {:?}", text)))
+}
+
+/// Nested objects that are sent as an empty array or null when missing.
+pub(crate) fn object_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned + Default,
+{
+    match Value::deserialize(deserializer)? {
+        value @ Value::Object(_) => serde_json::from_value(value).map_err(serde::de::Error::custom),
+        _ => Ok(T::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NovaResponse;
+
+    fn decode(json: &str) -> NovaResponse<serde_json::Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn failed_envelope_keeps_messages_and_codes() {
+        let response = decode(
+            r#"{
+                "success": false,
+                "data": [],
+                "errors": ["Document number is not correct"],
+                "warnings": {"20000900746": "Sender address is outdated"},
+                "info": {"totalCount": 0},
+                "errorCodes": [20000200068, "20000900746"],
+                "warningCodes": {"0": "20000900746"},
+                "infoCodes": ["", null, 5]
+            }"#,
+        );
+        let error = response.error().unwrap();
+        assert_eq!(error.errors, ["Document number is not correct"]);
+        assert_eq!(error.warnings, ["Sender address is outdated"]);
+        assert_eq!(error.error_codes, [20000200068, 20000900746]);
+        assert_eq!(error.warning_codes, [20000900746]);
+        assert_eq!(error.info["totalCount"], 0);
+        assert_eq!(response.info_codes, [5]);
+        assert_eq!(error.to_string(), "Document number is not correct");
+    }
+
+    #[test]
+    fn missing_and_odd_fields_default() {
+        let response = decode(r#"{"success": true, "errors": null, "warnings": "one"}"#);
+        assert!(response.error().is_none());
+        assert!(response.data.is_empty());
+        assert!(response.errors.is_empty());
+        assert_eq!(response.warnings, ["one"]);
+        assert!(response.error_codes.is_empty());
+
+        let response = decode(r#"{"success": false, "errors": [42, {"a": 1}]}"#);
+        assert_eq!(response.error().unwrap().errors, ["42", r#"{"a":1}"#]);
+    }
+}
@@ -0,0 +1,491 @@
+//! Local copy of the cities, settlements and warehouses directories.
+//!
+//! Every collection is kept in memory and, when the directory is opened from a path,
+//! in its own json file there, so lookups keep working while the api is slow or down.
+//! Collections are refreshed independently: one that fails to download keeps its
+//! previous contents.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::models::{
+    nearest_warehouses, NearbyWarehouse, NovaCity, NovaSettlement, WarehouseFilter,
+    CITIES_PAGE_LIMIT, SETTLEMENTS_PAGE_LIMIT, WAREHOUSES_PAGE_LIMIT,
+};
+use crate::{NovaPoshta, NovaWarehouse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collection {
+    Cities,
+    Settlements,
+    Warehouses,
+}
+
+impl Collection {
+    pub const ALL: [Collection; 3] = [
+        Collection::Cities,
+        Collection::Settlements,
+        Collection::Warehouses,
+    ];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Collection::Cities => "cities.json",
+            Collection::Settlements => "settlements.json",
+            Collection::Warehouses => "warehouses.json",
+        }
+    }
+
+    fn method(&self) -> (&'static str, &'static str, u32) {
+        match self {
+            Collection::Cities => ("Address", "getCities", CITIES_PAGE_LIMIT),
+            Collection::Settlements => ("AddressGeneral", "getSettlements", SETTLEMENTS_PAGE_LIMIT),
+            Collection::Warehouses => ("AddressGeneral", "getWarehouses", WAREHOUSES_PAGE_LIMIT),
+        }
+    }
+}
+
+/// File contents, items are kept exactly as the api sent them.
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    updated_at: DateTime<Utc>,
+    items: Vec<Value>,
+}
+
+#[derive(Debug, Clone)]
+struct Table<T> {
+    updated_at: Option<DateTime<Utc>>,
+    items: Vec<T>,
+    index: HashMap<String, usize>,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            updated_at: None,
+            items: vec![],
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Table<T> {
+    /// Items that fail to decode are logged and left out.
+    fn new(collection: Collection, stored: Stored, id: fn(&T) -> &str) -> Self {
+        let items: Vec<T> = stored
+            .items
+            .into_iter()
+            .filter_map(|item| match serde_json::from_value(item) {
+                Ok(item) => Some(item),
+                Err(e) => {
+                    warn!("skipping {:?} item: {}", collection, e);
+                    None
+                }
+            })
+            .collect();
+        let index = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (id(item).to_owned(), i))
+            .collect();
+        Table {
+            updated_at: Some(stored.updated_at),
+            items,
+            index,
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<&T> {
+        self.index.get(id).map(|&i| &self.items[i])
+    }
+}
+
+/// Freshly downloaded collection waiting to replace the current one.
+enum Update {
+    Cities(Table<NovaCity>),
+    Settlements(Table<NovaSettlement>),
+    Warehouses(Table<NovaWarehouse>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Directory {
+    path: Option<PathBuf>,
+    cities: Table<NovaCity>,
+    settlements: Table<NovaSettlement>,
+    warehouses: Table<NovaWarehouse>,
+}
+
+impl Directory {
+    /// Empty directory that is never written to disk.
+    pub fn in_memory() -> Self {
+        Directory::default()
+    }
+
+    /// Loads collections saved in `path` before, missing ones stay empty until refreshed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        let mut directory = Directory {
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+        for collection in Collection::ALL {
+            let file = path.join(collection.file_name());
+            if !file.exists() {
+                continue;
+            }
+            let stored: Stored = serde_json::from_slice(&fs::read(&file)?)?;
+            directory.apply(Self::decode(collection, stored));
+        }
+        Ok(directory)
+    }
+
+    pub fn updated_at(&self, collection: Collection) -> Option<DateTime<Utc>> {
+        match collection {
+            Collection::Cities => self.cities.updated_at,
+            Collection::Settlements => self.settlements.updated_at,
+            Collection::Warehouses => self.warehouses.updated_at,
+        }
+    }
+
+    /// Collections never downloaded or downloaded more than `max_age` ago.
+    pub fn stale(&self, max_age: Duration) -> Vec<Collection> {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        let now = Utc::now();
+        Collection::ALL
+            .into_iter()
+            .filter(|c| self.updated_at(*c).is_none_or(|at| now - at >= max_age))
+            .collect()
+    }
+
+    /// Downloads every collection again.
+    pub async fn refresh(&mut self, nova: &NovaPoshta) -> Result<()> {
+        self.refresh_older_than(nova, Duration::ZERO).await
+    }
+
+    /// Downloads collections older than `max_age`, returning the first failure after
+    /// trying all of them.
+    pub async fn refresh_older_than(&mut self, nova: &NovaPoshta, max_age: Duration) -> Result<()> {
+        let path = self.path.clone();
+        download_all(nova, self.stale(max_age), path.as_deref(), |update| {
+            self.apply(update)
+        })
+        .await
+    }
+
+    async fn download(
+        nova: &NovaPoshta,
+        collection: Collection,
+        path: Option<&Path>,
+    ) -> Result<Update> {
+        let (model, method, limit) = collection.method();
+        let items: Vec<Value> = nova
+            .pages_stream(model, method, json!({}), 1, limit)
+            .try_collect()
+            .await?;
+        let stored = Stored {
+            updated_at: Utc::now(),
+            items,
+        };
+        let stored = match path {
+            // Writing thousands of warehouses would stall the runtime the refresh runs on
+            Some(path) => {
+                let path = path.to_owned();
+                tokio::task::spawn_blocking(move || {
+                    Self::save(&path, collection, &stored).map(|()| stored)
+                })
+                .await
+                .map_err(std::io::Error::from)??
+            }
+            None => stored,
+        };
+        Ok(Self::decode(collection, stored))
+    }
+
+    /// Replaces the file in one rename, so a crash never leaves half of it behind.
+    fn save(path: &Path, collection: Collection, stored: &Stored) -> Result<()> {
+        let file = path.join(collection.file_name());
+        let tmp = file.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(stored)?)?;
+        fs::rename(&tmp, &file)?;
+        Ok(())
+    }
+
+    fn decode(collection: Collection, stored: Stored) -> Update {
+        match collection {
+            Collection::Cities => {
+                Update::Cities(Table::new(collection, stored, |c: &NovaCity| &c.id))
+            }
+            Collection::Settlements => {
+                Update::Settlements(Table::new(collection, stored, |s: &NovaSettlement| &s.id))
+            }
+            Collection::Warehouses => {
+                Update::Warehouses(Table::new(collection, stored, |w: &NovaWarehouse| &w.id))
+            }
+        }
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Cities(table) => self.cities = table,
+            Update::Settlements(table) => self.settlements = table,
+            Update::Warehouses(table) => self.warehouses = table,
+        }
+    }
+
+    pub fn cities(&self) -> &[NovaCity] {
+        &self.cities.items
+    }
+
+    pub fn city(&self, city_ref: &str) -> Option<&NovaCity> {
+        self.cities.get(city_ref)
+    }
+
+    /// Cities whose ukrainian or russian name starts with `name`, ignoring case.
+    pub fn find_cities(&self, name: &str) -> Vec<&NovaCity> {
+        let name = name.to_lowercase();
+        self.cities
+            .items
+            .iter()
+            .filter(|c| {
+                c.description.to_lowercase().starts_with(&name)
+                    || c.description_ru.to_lowercase().starts_with(&name)
+            })
+            .collect()
+    }
+
+    pub fn settlements(&self) -> &[NovaSettlement] {
+        &self.settlements.items
+    }
+
+    pub fn settlement(&self, settlement_ref: &str) -> Option<&NovaSettlement> {
+        self.settlements.get(settlement_ref)
+    }
+
+    pub fn warehouses(&self) -> &[NovaWarehouse] {
+        &self.warehouses.items
+    }
+
+    pub fn warehouse(&self, warehouse_ref: &str) -> Option<&NovaWarehouse> {
+        self.warehouses.get(warehouse_ref)
+    }
+
+    pub fn city_warehouses(&self, city_ref: &str) -> Vec<&NovaWarehouse> {
+        self.warehouses
+            .items
+            .iter()
+            .filter(|w| w.city_ref == city_ref)
+            .collect()
+    }
+
+    /// Warehouses within `radius_km` of a point, closest first.
+    pub fn nearest_warehouses(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: &WarehouseFilter,
+    ) -> Vec<NearbyWarehouse> {
+        nearest_warehouses(
+            &self.warehouses.items,
+            latitude,
+            longitude,
+            radius_km,
+            filter,
+        )
+    }
+}
+
+/// Downloads every collection in turn, handing each one to `apply` as soon as it
+/// arrives, and returns the first failure after trying all of them.
+async fn download_all(
+    nova: &NovaPoshta,
+    collections: Vec<Collection>,
+    path: Option<&Path>,
+    mut apply: impl FnMut(Update),
+) -> Result<()> {
+    let mut result = Ok(());
+    for collection in collections {
+        match Directory::download(nova, collection, path).await {
+            Ok(update) => apply(update),
+            Err(e) => {
+                warn!("failed to refresh {:?}: {}", collection, e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// A [`Directory`] shared between tasks, refreshed without blocking readers.
+#[derive(Debug, Clone, Default)]
+pub struct SharedDirectory {
+    inner: Arc<RwLock<Directory>>,
+}
+
+impl SharedDirectory {
+    pub fn new(directory: Directory) -> Self {
+        SharedDirectory {
+            inner: Arc::new(RwLock::new(directory)),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Directory> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Same as [`Directory::refresh_older_than`], readers only wait for the swap.
+    pub async fn refresh_older_than(&self, nova: &NovaPoshta, max_age: Duration) -> Result<()> {
+        let (stale, path) = {
+            let directory = self.read();
+            (directory.stale(max_age), directory.path.clone())
+        };
+        download_all(nova, stale, path.as_deref(), |update| {
+            self.inner
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .apply(update)
+        })
+        .await
+    }
+
+    /// Refreshes collections older than `every` in the background, forever.
+    pub fn spawn_refresh(&self, nova: NovaPoshta, every: Duration) -> JoinHandle<()> {
+        let directory = self.clone();
+        tokio::spawn(async move {
+            loop {
+                // Failures are logged and retried on the next tick.
+                let _ = directory.refresh_older_than(&nova, every).await;
+                tokio::time::sleep(every).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_api;
+
+    /// Fresh directory under the system temp dir, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("novaposhta-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn city(id: &str, name: &str) -> Value {
+        json!({
+            "Ref": id,
+            "Description": name,
+            "Area": "71508131-9b87-11de-822f-000c2965ae0e",
+            "SettlementType": "563ced10-f210-11e3-8c4a-0050568002cf",
+            "Delivery1": "1"
+        })
+    }
+
+    #[test]
+    fn saved_collections_open_again() {
+        let dir = TempDir::new("round-trip");
+        fs::create_dir_all(&dir.0).unwrap();
+        let updated_at = Utc::now();
+        let stored = Stored {
+            updated_at,
+            items: vec![
+                city("db5c88f5-391c-11dd-90d9-001a92567626", "Львів"),
+                // Missing required fields, must not take the others down with it.
+                json!({ "Ref": "broken" }),
+                city("8d5a980d-391c-11dd-90d9-001a92567626", "Київ"),
+            ],
+        };
+        Directory::save(&dir.0, Collection::Cities, &stored).unwrap();
+        assert!(!dir.0.join("cities.json.tmp").exists());
+
+        let directory = Directory::open(&dir.0).unwrap();
+        assert_eq!(directory.cities().len(), 2);
+        assert_eq!(directory.updated_at(Collection::Cities), Some(updated_at));
+        let kyiv = directory
+            .city("8d5a980d-391c-11dd-90d9-001a92567626")
+            .unwrap();
+        assert_eq!(kyiv.description, "Київ");
+        assert!(kyiv.delivery_monday);
+        assert!(directory.city("broken").is_none());
+        assert_eq!(directory.find_cities("льв").len(), 1);
+        assert!(directory.warehouses().is_empty());
+        assert_eq!(directory.updated_at(Collection::Warehouses), None);
+    }
+
+    #[test]
+    fn stale_lists_missing_and_old_collections() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(Directory::in_memory().stale(day), Collection::ALL.to_vec());
+
+        let dir = TempDir::new("stale");
+        fs::create_dir_all(&dir.0).unwrap();
+        let fresh = Stored {
+            updated_at: Utc::now(),
+            items: vec![],
+        };
+        let old = Stored {
+            updated_at: Utc::now() - chrono::Duration::days(2),
+            items: vec![],
+        };
+        Directory::save(&dir.0, Collection::Cities, &fresh).unwrap();
+        Directory::save(&dir.0, Collection::Warehouses, &old).unwrap();
+
+        let directory = Directory::open(&dir.0).unwrap();
+        assert_eq!(
+            directory.stale(day),
+            vec![Collection::Settlements, Collection::Warehouses]
+        );
+        assert_eq!(directory.stale(3 * day), vec![Collection::Settlements]);
+        assert_eq!(directory.stale(Duration::ZERO), Collection::ALL.to_vec());
+    }
+
+    #[tokio::test]
+    async fn refresh_saves_downloaded_collections() {
+        let dir = TempDir::new("refresh");
+        let (nova, requests) = mock_api(|request| match request["calledMethod"].as_str() {
+            Some("getCities") => json!([city("8d5a980d-391c-11dd-90d9-001a92567626", "Київ")]),
+            _ => json!([]),
+        })
+        .await;
+        let shared = SharedDirectory::new(Directory::open(&dir.0).unwrap());
+        shared
+            .refresh_older_than(&nova, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(requests.all().len(), Collection::ALL.len());
+        assert_eq!(shared.read().cities().len(), 1);
+
+        let reopened = Directory::open(&dir.0).unwrap();
+        assert_eq!(reopened.cities().len(), 1);
+        assert!(reopened.stale(Duration::from_secs(60)).is_empty());
+        assert!(!dir.0.join("cities.json.tmp").exists());
+    }
+}
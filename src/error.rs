@@ -1,27 +1,174 @@
 use std::error::Error;
-use std::fmt::{Display, Formatter, Result};
+use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Clone)]
-pub struct NovaRequestError {
-    details: String,
+use reqwest::StatusCode;
+
+pub type Result<T, E = NovaError> = std::result::Result<T, E>;
+
+/// Every way a call to the Nova Poshta api can fail.
+#[derive(Debug)]
+pub enum NovaError {
+    /// Connection, tls or timeout failure before a response was received.
+    Transport(reqwest::Error),
+    /// The server answered with a non-success http status.
+    Status { status: StatusCode, body: String },
+    /// The response body is not the json we expected.
+    Decode(serde_json::Error),
+    /// The api answered with `success: false`.
+    Api(Box<NovaApiError>),
+    /// The request was rejected locally before it was sent.
+    Validation(ValidationError),
+    /// A required environment variable is not set.
+    MissingEnv(&'static str),
+    /// An environment variable is set to a value we cannot use.
+    InvalidEnv { name: &'static str, value: String },
+    /// Reading or writing a local file failed.
+    Io(std::io::Error),
+}
+
+/// Errors reported by the api itself in the response envelope.
+#[derive(Debug, Clone, Default)]
+pub struct NovaApiError {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub error_codes: Vec<u64>,
+    pub warning_codes: Vec<u64>,
+    pub info: serde_json::Value,
+}
+
+impl Display for NovaApiError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.errors.is_empty() {
+            write!(f, "api request failed without error message")
+        } else {
+            write!(f, "{}", self.errors.join("; "))
+        }
+    }
+}
+
+impl Error for NovaApiError {}
+
+/// A single field that is missing or contradicts another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the field, like `recipient.address.address_house`.
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found in a request, not just the first one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationError {
+    pub fields: Vec<FieldError>,
+}
+
+impl ValidationError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        let mut error = ValidationError::default();
+        error.push(field, message);
+        error
+    }
+
+    pub fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.fields.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn extend(&mut self, other: ValidationError) {
+        self.fields.extend(other.fields);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// `Ok` when nothing was pushed.
+    pub fn into_result(self) -> Result<(), ValidationError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
 }
 
-impl NovaRequestError {
-    pub fn new(msg: String) -> NovaRequestError {
-        NovaRequestError {
-            details: msg.to_string(),
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        write!(f, "{}", fields.join("; "))
+    }
+}
+
+impl Error for ValidationError {}
+
+impl Display for NovaError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            NovaError::Transport(e) => write!(f, "transport error: {}", e),
+            NovaError::Status { status, body } => write!(f, "http {}: {}", status, body),
+            NovaError::Decode(e) => write!(f, "failed to decode response: {}", e),
+            NovaError::Api(e) => write!(f, "api error: {}", e),
+            NovaError::Validation(e) => write!(f, "invalid request: {}", e),
+            NovaError::MissingEnv(name) => write!(f, "environment variable {} is not set", name),
+            NovaError::InvalidEnv { name, value } => {
+                write!(
+                    f,
+                    "environment variable {} has invalid value {:?}",
+                    name, value
+                )
+            }
+            NovaError::Io(e) => write!(f, "io error: {}", e),
         }
     }
 }
 
-impl Display for NovaRequestError {
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f, "{}", self.details)
+impl Error for NovaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NovaError::Transport(e) => Some(e),
+            NovaError::Decode(e) => Some(e),
+            NovaError::Api(e) => Some(e.as_ref()),
+            NovaError::Io(e) => Some(e),
+            NovaError::Validation(e) => Some(e),
+            NovaError::Status { .. } | NovaError::MissingEnv(_) | NovaError::InvalidEnv { .. } => {
+                None
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for NovaError {
+    fn from(e: reqwest::Error) -> Self {
+        NovaError::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for NovaError {
+    fn from(e: serde_json::Error) -> Self {
+        NovaError::Decode(e)
+    }
+}
+
+impl From<ValidationError> for NovaError {
+    fn from(e: ValidationError) -> Self {
+        NovaError::Validation(e)
+    }
+}
+
+impl From<std::io::Error> for NovaError {
+    fn from(e: std::io::Error) -> Self {
+        NovaError::Io(e)
     }
 }
 
-impl Error for NovaRequestError {
-    fn description(&self) -> &str {
-        &self.details
+impl From<NovaApiError> for NovaError {
+    fn from(e: NovaApiError) -> Self {
+        NovaError::Api(Box::new(e))
     }
 }
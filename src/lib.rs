@@ -1,36 +1,85 @@
+mod builder;
+mod de;
+#[cfg(feature = "directory")]
+pub mod directory;
 pub mod error;
+mod limit;
 pub mod models;
 pub mod raw;
+mod retry;
+#[cfg(test)]
+mod testing;
 pub mod types;
 
-use anyhow::Result;
+pub use builder::{NovaLanguage, NovaPoshtaBuilder, DEFAULT_BASE_URL};
+pub use limit::{RateLimiter, RatePermit};
+pub use retry::{Retry, RetryPolicy};
+
 use chrono::Duration;
+use error::{NovaApiError, NovaError, Result, ValidationError};
+use futures::future;
+use futures::stream::{self, Stream, TryStreamExt};
 use log::debug;
 use models::*;
-use reqwest::Client;
+use reqwest::{header::HeaderMap, Client};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct NovaPoshta {
     api_key: String,
     client: Client,
+    base_url: String,
+    timeout: Option<std::time::Duration>,
+    headers: HeaderMap,
+    language: Option<NovaLanguage>,
+    retry_policy: RetryPolicy,
+    retry: Retry,
+    limiter: Option<Arc<RateLimiter>>,
 }
 
 impl NovaPoshta {
     pub fn new(api_key: String) -> Self {
-        let client = Client::new();
-        NovaPoshta { client, api_key }
+        NovaPoshta {
+            client: Client::new(),
+            api_key,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: None,
+            headers: HeaderMap::new(),
+            language: None,
+            retry_policy: RetryPolicy::default(),
+            retry: Retry::Auto,
+            limiter: None,
+        }
+    }
+
+    pub fn builder(api_key: impl Into<String>) -> NovaPoshtaBuilder {
+        NovaPoshtaBuilder::new(api_key)
+    }
+
+    /// Builds a client from `NOVAPOSHTA_KEY` and the optional `NOVAPOSHTA_BASE_URL`,
+    /// `NOVAPOSHTA_TIMEOUT` and `NOVAPOSHTA_LANGUAGE` variables.
+    pub fn from_env() -> Result<Self> {
+        NovaPoshtaBuilder::from_env()?.build()
+    }
+
+    /// Copy of this client that applies `retry` to every call, e.g.
+    /// `nova.with_retry(Retry::Never).new_shipment(..)`.
+    pub fn with_retry(&self, retry: Retry) -> Self {
+        NovaPoshta {
+            retry,
+            ..self.clone()
+        }
     }
 }
 
+/// Panics when the environment is misconfigured, prefer [`NovaPoshta::from_env`].
 impl Default for NovaPoshta {
     fn default() -> Self {
-        let client = Client::new();
-        NovaPoshta {
-            client,
-            api_key: std::env::var("NOVAPOSHTA_KEY").unwrap(),
-        }
+        NovaPoshta::from_env().expect("failed to configure NovaPoshta from environment")
     }
 }
 
@@ -44,60 +93,447 @@ impl NovaPoshta {
     where
         T: DeserializeOwned + Debug,
     {
+        self.retrying(model, method, || async {
+            let response = self.send(model, method, payload.clone()).await?;
+            match response.error() {
+                Some(e) => Err(e.into()),
+                None => Ok(response),
+            }
+        })
+        .await
+    }
+
+    /// Calls any api method and returns the envelope as is, even when `success` is false.
+    pub async fn call_raw<T>(
+        &self,
+        model: &str,
+        method: &str,
+        payload: serde_json::Value,
+    ) -> Result<NovaResponse<T>>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        self.retrying(model, method, || self.send(model, method, payload.clone()))
+            .await
+    }
+
+    /// Walks pages starting at `first` until one comes back shorter than `limit`, a zero
+    /// `limit` fetches only the first page.
+    fn paginate<'a, T, F, Fut>(
+        &'a self,
+        first: u32,
+        limit: u32,
+        fetch: F,
+    ) -> impl Stream<Item = Result<T>> + 'a
+    where
+        T: 'a,
+        F: FnMut(u32) -> Fut + 'a,
+        Fut: Future<Output = Result<Vec<T>>> + 'a,
+    {
+        stream::try_unfold((Some(first), fetch), move |(page, mut fetch)| async move {
+            let Some(page) = page else {
+                return Ok::<_, NovaError>(None);
+            };
+            let items = fetch(page).await?;
+            let next = (limit > 0 && items.len() >= limit as usize).then_some(page + 1);
+            Ok(Some((
+                stream::iter(items.into_iter().map(Ok::<T, NovaError>)),
+                (next, fetch),
+            )))
+        })
+        .try_flatten()
+    }
+
+    /// Every item of a paged reference book, `payload` gets `Page` and `Limit` added.
+    pub(crate) fn pages_stream<'a, T>(
+        &'a self,
+        model: &'a str,
+        method: &'a str,
+        payload: serde_json::Value,
+        first: u32,
+        limit: u32,
+    ) -> impl Stream<Item = Result<T>> + 'a
+    where
+        T: DeserializeOwned + Debug + 'a,
+    {
+        self.paginate(first, limit, move |page| {
+            let mut payload = payload.clone();
+            if let Some(properties) = payload.as_object_mut() {
+                properties.insert("Page".to_string(), page.into());
+                properties.insert("Limit".to_string(), limit.into());
+            }
+            async move { Ok(self.run(model, method, payload).await?.data) }
+        })
+    }
+
+    async fn retrying<T, F, Fut>(&self, model: &str, method: &str, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let retry = self.retry.resolve(model, method);
+        let mut attempts = 1;
+        loop {
+            match attempt().await {
+                Err(e)
+                    if attempts < self.retry_policy.max_attempts
+                        && retry.should_retry(&e, &self.retry_policy) =>
+                {
+                    let delay = self.retry_policy.delay(attempts);
+                    debug!(
+                        "{}.{} attempt {} failed, retrying in {:?}: {}",
+                        model, method, attempts, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send<T>(
+        &self,
+        model: &str,
+        method: &str,
+        payload: serde_json::Value,
+    ) -> Result<NovaResponse<T>>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let mut payload = payload;
+        if let (Some(language), Some(properties)) = (self.language, payload.as_object_mut()) {
+            properties
+                .entry("Language")
+                .or_insert_with(|| language.code().into());
+        }
         let payload = json!({
             "calledMethod": method,
             "modelName": model,
             "methodProperties": payload,
             "apiKey": self.api_key
         });
-        let response: NovaResponse<T> = self
+        let mut request = self
             .client
-            .post("https://api.novaposhta.ua/v2.0/json/")
-            .json(&payload)
-            .send()
-            .await?
-            .json::<NovaResponse<T>>()
-            .await?;
+            .post(&self.base_url)
+            .headers(self.headers.clone())
+            .json(&payload);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        };
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(NovaError::Status {
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+        let response: NovaResponse<T> = serde_json::from_slice(&body)?;
         debug!("{:#?}", response);
         Ok(response)
     }
 
-    // Query like Львів, 100
-    pub async fn get_warehouses(&self, query: String) -> Result<NovaResponse<Vec<NovaWarehouse>>> {
-        Ok(self
-            .run(
-                "AddressGeneral",
-                "getWarehouses",
-                json!({ "FindByString": query }),
-            )
-            .await?)
+    pub async fn get_cities(&self, query: CityQuery) -> Result<NovaResponse<NovaCity>> {
+        let mut response: NovaResponse<NovaCity> = self
+            .run("Address", "getCities", serde_json::to_value(&query)?)
+            .await?;
+        if let Some(area) = &query.area_id {
+            response.data.retain(|city| &city.area_id == area);
+        }
+        Ok(response)
     }
 
-    // Search your counterparties
-    pub async fn get_counterpartys(&self) -> Result<NovaResponse<Vec<NovaCounterparty>>> {
-        Ok(self
-            .run(
-                "Counterparty",
-                "getCounterparties",
-                json!({
-                    "CounterpartyProperty": "Sender"
-                }),
-            )
-            .await?)
+    /// Every city matching `query`, fetched page by page as the stream is polled. A zero
+    /// limit is treated as unset.
+    ///
+    /// The area filter is applied to the stream, so pages with no city of the area do
+    /// not end it early.
+    pub fn cities_stream(&self, query: CityQuery) -> impl Stream<Item = Result<NovaCity>> + '_ {
+        let limit = query.limit.filter(|&l| l > 0).unwrap_or(CITIES_PAGE_LIMIT);
+        let first = query.page.unwrap_or(1);
+        let area = query.area_id.clone();
+        self.pages_stream("Address", "getCities", json!(query), first, limit)
+            .try_filter(move |city: &NovaCity| {
+                future::ready(area.as_ref().is_none_or(|area| &city.area_id == area))
+            })
+    }
+
+    pub async fn get_settlements(
+        &self,
+        page: u32,
+        limit: u32,
+    ) -> Result<NovaResponse<NovaSettlement>> {
+        self.run(
+            "AddressGeneral",
+            "getSettlements",
+            json!({
+                "Page": page,
+                "Limit": limit
+            }),
+        )
+        .await
+    }
+
+    /// Every settlement in the country.
+    pub fn settlements_stream(&self) -> impl Stream<Item = Result<NovaSettlement>> + '_ {
+        self.pages_stream(
+            "AddressGeneral",
+            "getSettlements",
+            json!({}),
+            1,
+            SETTLEMENTS_PAGE_LIMIT,
+        )
+    }
+
+    // Autocomplete for settlement names, like Льв
+    pub async fn search_settlements(
+        &self,
+        query: &str,
+        limit: u32,
+        page: u32,
+    ) -> Result<NovaResponse<NovaSettlementSearch<NovaSettlementAddress>>> {
+        self.run(
+            "Address",
+            "searchSettlements",
+            json!({
+                "CityName": query,
+                "Limit": limit,
+                "Page": page
+            }),
+        )
+        .await
+    }
+
+    // Autocomplete for streets of a settlement found by `search_settlements`
+    pub async fn search_settlement_streets(
+        &self,
+        settlement_ref: &str,
+        query: &str,
+        limit: u32,
+    ) -> Result<NovaResponse<NovaSettlementSearch<NovaSettlementStreet>>> {
+        self.run(
+            "Address",
+            "searchSettlementStreets",
+            json!({
+                "SettlementRef": settlement_ref,
+                "StreetName": query,
+                "Limit": limit
+            }),
+        )
+        .await
+    }
+
+    // Streets of a city from `get_cities`, query like Хрещ
+    pub async fn get_streets(
+        &self,
+        city_ref: &str,
+        query: &str,
+    ) -> Result<NovaResponse<NovaStreet>> {
+        self.run(
+            "Address",
+            "getStreet",
+            json!({
+                "CityRef": city_ref,
+                "FindByString": query
+            }),
+        )
+        .await
+    }
+
+    // Query like Львів, 100 or a `WarehouseQuery` with filters
+    pub async fn get_warehouses(
+        &self,
+        query: impl Into<WarehouseQuery>,
+    ) -> Result<NovaResponse<NovaWarehouse>> {
+        self.run(
+            "AddressGeneral",
+            "getWarehouses",
+            serde_json::to_value(query.into())?,
+        )
+        .await
+    }
+
+    /// Warehouses matching `query` within `radius_km` of a point, closest first.
+    ///
+    /// Narrow `query` to a city or settlement, every page of it is downloaded.
+    pub async fn nearest_warehouses(
+        &self,
+        query: impl Into<WarehouseQuery>,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: &WarehouseFilter,
+    ) -> Result<Vec<NearbyWarehouse>> {
+        let warehouses: Vec<NovaWarehouse> = self.warehouses_stream(query).try_collect().await?;
+        Ok(models::nearest_warehouses(
+            &warehouses,
+            latitude,
+            longitude,
+            radius_km,
+            filter,
+        ))
+    }
+
+    /// Every warehouse matching `query`, fetched page by page as the stream is polled. A
+    /// zero limit is treated as unset.
+    pub fn warehouses_stream(
+        &self,
+        query: impl Into<WarehouseQuery>,
+    ) -> impl Stream<Item = Result<NovaWarehouse>> + '_ {
+        let query = query.into();
+        let limit = query
+            .limit
+            .filter(|&l| l > 0)
+            .unwrap_or(WAREHOUSES_PAGE_LIMIT);
+        let first = query.page.unwrap_or(1);
+        self.pages_stream(
+            "AddressGeneral",
+            "getWarehouses",
+            json!(query),
+            first,
+            limit,
+        )
+    }
+
+    // First page of your senders
+    #[deprecated(note = "use search_counterparties")]
+    pub async fn get_counterpartys(&self) -> Result<NovaResponse<NovaCounterparty>> {
+        self.search_counterparties(NovaCounterpartyProperty::Sender, None, 1)
+            .await
+    }
+
+    // Counterparties stored in the cabinet, optionally filtered by name or EDRPOU
+    pub async fn search_counterparties(
+        &self,
+        property: NovaCounterpartyProperty,
+        find_by_string: Option<&str>,
+        page: u32,
+    ) -> Result<NovaResponse<NovaCounterparty>> {
+        let mut payload = counterparties_query(property, find_by_string);
+        payload["Page"] = json!(page);
+        self.run("Counterparty", "getCounterparties", payload).await
+    }
+
+    /// Every counterparty with `property`, page by page.
+    pub fn counterparties_stream(
+        &self,
+        property: NovaCounterpartyProperty,
+        find_by_string: Option<String>,
+    ) -> impl Stream<Item = Result<NovaCounterparty>> + '_ {
+        self.pages_stream(
+            "Counterparty",
+            "getCounterparties",
+            counterparties_query(property, find_by_string.as_deref()),
+            1,
+            COUNTERPARTIES_PAGE_LIMIT,
+        )
+    }
+
+    pub async fn get_counterparty_addresses(
+        &self,
+        counterparty_ref: &str,
+        property: NovaCounterpartyProperty,
+    ) -> Result<NovaResponse<NovaCounterpartyAddress>> {
+        self.run(
+            "Counterparty",
+            "getCounterpartyAddresses",
+            json!({
+                "Ref": counterparty_ref,
+                "CounterpartyProperty": property
+            }),
+        )
+        .await
+    }
+
+    pub async fn get_counterparty_options(
+        &self,
+        counterparty_ref: &str,
+    ) -> Result<NovaResponse<NovaCounterpartyOptions>> {
+        self.run(
+            "Counterparty",
+            "getCounterpartyOptions",
+            json!({ "Ref": counterparty_ref }),
+        )
+        .await
+    }
+
+    // Organisation by its EDRPOU code, as registered in `city_ref`
+    pub async fn get_counterparty_by_edrpou(
+        &self,
+        edrpou: &str,
+        city_ref: &str,
+    ) -> Result<NovaResponse<NovaCounterparty>> {
+        self.run(
+            "Counterparty",
+            "getCounterpartyByEDRPOU",
+            json!({
+                "EDRPOU": edrpou,
+                "CityRef": city_ref
+            }),
+        )
+        .await
     }
 
     // Search counterparty contact persons
     pub async fn get_counterparty_contact_person(
         &self,
         id: String,
-    ) -> Result<NovaResponse<Vec<NovaCounterparty>>> {
-        Ok(self
-            .run(
-                "Counterparty",
-                "getCounterpartyContactPersons",
-                json!({ "Ref": id }),
-            )
-            .await?)
+    ) -> Result<NovaResponse<NovaContactPerson>> {
+        self.run(
+            "Counterparty",
+            "getCounterpartyContactPersons",
+            json!({ "Ref": id }),
+        )
+        .await
+    }
+
+    /// Stores a private person or an organisation so later shipments can use its ref.
+    pub async fn create_counterparty(
+        &self,
+        counterparty: NewCounterparty,
+    ) -> Result<NovaResponse<NovaCounterparty>> {
+        counterparty.validate()?;
+        self.run("Counterparty", "save", serde_json::to_value(counterparty)?)
+            .await
+    }
+
+    pub async fn create_contact_person(
+        &self,
+        counterparty_ref: &str,
+        person: &ContactPerson,
+    ) -> Result<NovaResponse<NovaContactPerson>> {
+        person.validate()?;
+        let mut payload = serde_json::to_value(person)?;
+        payload["CounterpartyRef"] = json!(counterparty_ref);
+        self.run("ContactPerson", "save", payload).await
+    }
+
+    pub async fn update_contact_person(
+        &self,
+        counterparty_ref: &str,
+        contact_ref: &str,
+        person: &ContactPerson,
+    ) -> Result<NovaResponse<NovaContactPerson>> {
+        person.validate()?;
+        let mut payload = serde_json::to_value(person)?;
+        payload["CounterpartyRef"] = json!(counterparty_ref);
+        payload["Ref"] = json!(contact_ref);
+        self.run("ContactPerson", "update", payload).await
+    }
+
+    /// Only the ref of the deleted contact person is filled in.
+    pub async fn delete_contact_person(
+        &self,
+        contact_ref: &str,
+    ) -> Result<NovaResponse<NovaContactPerson>> {
+        self.run("ContactPerson", "delete", json!({ "Ref": contact_ref }))
+            .await
     }
 
     pub async fn new_shipment(
@@ -108,63 +544,290 @@ impl NovaPoshta {
         date_of_send: Option<chrono::DateTime<chrono::Local>>,
     ) -> Result<NovaResponse<NovaShipmentCreated>> {
         let seats_amount = cargos.len();
-        if seats_amount <= 0 {
-            return Err(anyhow::anyhow!("You cannot send 0 cargos"));
+        let mut errors = ValidationError::default();
+        if let Err(cargos) = cargos.validate() {
+            errors.extend(cargos);
+        }
+        if let Err(recipient) = recipient.validate() {
+            errors.extend(recipient);
         }
+        if let Err(sender) = sender.validate() {
+            errors.extend(sender);
+        }
+        errors.into_result()?;
+        let sender_address = match &sender.address {
+            SenderAddress::Warehouse(id) | SenderAddress::SavedDoor(id) => id.clone(),
+            SenderAddress::Door {
+                street_ref,
+                house,
+                flat,
+            } => {
+                self.door_address(
+                    &sender.counterparty_id,
+                    NovaCounterpartyProperty::Sender,
+                    street_ref,
+                    house,
+                    flat.as_deref(),
+                )
+                .await?
+            }
+        };
+        let date_of_send = date_of_send.unwrap_or_default().into_ttn_time();
+        let mut address_name = None;
+        let mut address_ref = None;
+        let mut house = None;
+        let mut flat = None;
+        let mut warehouse_index = None;
+        match &recipient.address {
+            Address::Warehouse(WarehouseId::Number(number)) => address_name = Some(number.clone()),
+            Address::Warehouse(id @ WarehouseId::Ref(_)) => {
+                // With NewAddress the api reads the warehouse number, the ref alone is ignored
+                let warehouse = self.find_warehouse(&recipient.city_name, id).await?;
+                address_name = Some(warehouse.number);
+                address_ref = Some(warehouse.id);
+            }
+            Address::Postomat(id) => {
+                let postomat = self.find_postomat(&recipient.city_name, id).await?;
+                postomat.check_cargos(&cargos)?;
+                address_name = Some(postomat.number);
+                address_ref = Some(postomat.id);
+                warehouse_index = Some(postomat.warehouse_index);
+            }
+            Address::Door {
+                street: StreetId::Name(name),
+                house: h,
+                flat: f,
+            } => {
+                address_name = Some(name.clone());
+                house = Some(h.clone());
+                flat = f.clone();
+            }
+            Address::Door {
+                street: StreetId::Ref(street_ref),
+                house,
+                flat,
+            } => {
+                let counterparty = recipient.counterparty.as_ref().ok_or_else(|| {
+                    ValidationError::new(
+                        "recipient.counterparty",
+                        "is required for door delivery to a street ref",
+                    )
+                })?;
+                address_ref = Some(
+                    self.door_address(
+                        &counterparty.counterparty_id,
+                        NovaCounterpartyProperty::Recipient,
+                        street_ref,
+                        house,
+                        flat.as_deref(),
+                    )
+                    .await?,
+                );
+            }
+            Address::SavedDoor(saved) => address_ref = Some(saved.clone()),
+        }
+        let options_seat = cargos.options_seat();
         let (weight, price, to_payment, description) = cargos.into_ttn_values();
-        let payer = if recipient.is_payer {
-            "Recipient"
-        } else {
-            "Sender"
+        let third_person = match &recipient.payer {
+            NovaPayer::ThirdPerson(id) => Some(id),
+            _ => None,
         };
-        let backward_delivery = if to_payment > 0 {
+        let organization = recipient.organization.as_ref();
+        let backward_delivery = if !to_payment.is_zero() {
             vec![json!({
                 "PayerType": "Recipient",
-                "CargoType": "Money",
+                "CargoType": NovaCargoType::Money,
                 "RedeliveryString": to_payment
             })]
         } else {
             vec![]
         };
-        let payload = json!({
+        let mut payload = json!({
             "NewAddress": "1",
-            "PayerType": payer,
+            "PayerType": recipient.payer.payer_type(),
             "Weight": weight,
-            "CargoType": "Parcel",
-            "ServiceType": recipient.address.service_type,
+            "CargoType": cargos.cargo_type(),
+            "CargoDetails": cargos.cargo_details(),
+            "ServiceType": recipient.address.service_type_from(sender.is_door()),
             "SeatsAmount": seats_amount,
             "Description": description,
             "Cost": price,
             "CitySender": sender.city_id,
-            "SenderAddress": sender.warehouse_id,
+            "SenderAddress": sender_address,
             "Sender": sender.counterparty_id,
             "ContactSender": sender.contact_id,
             "SendersPhone": sender.contact_phone,
-            "RecipientAddressName": recipient.address.address_name.unwrap_or(recipient.address.warehouse_number.unwrap()),
-            "RecipientHouse": recipient.address.address_house,
-            "RecipientFlat": recipient.address.address_flat,
+            "OptionsSeat": options_seat,
             "RecipientCityName": recipient.city_name,
             "RecipientName": recipient.full_name,
-            "RecipientType": "PrivatePerson",
+            "RecipientType": recipient.recipient_type(),
             "RecipientsPhone": recipient.phone,
-            "DateTime": date_of_send.unwrap_or_default().into_ttn_time(),
-            "PaymentMethod": "Cash",
+            "DateTime": date_of_send,
+            "PaymentMethod": recipient.payment_method,
             "BackwardDeliveryData": backward_delivery
         });
-        return Ok(self.run("InternetDocument", "save", payload).await?);
+        let optional = [
+            ("ThirdPerson", third_person.cloned()),
+            ("RecipientAddressName", address_name),
+            ("RecipientAddress", address_ref),
+            ("RecipientWarehouseIndex", warehouse_index),
+            ("RecipientHouse", house),
+            ("RecipientFlat", flat),
+            ("RecipientArea", recipient.area.clone()),
+            ("RecipientAreaRegions", recipient.region.clone()),
+            ("SettlementType", recipient.settlement_type.clone()),
+            ("EDRPOU", organization.map(|o| o.edrpou.clone())),
+            (
+                "OwnershipForm",
+                organization.map(|o| o.ownership_form.clone()),
+            ),
+            (
+                "RecipientContactName",
+                organization.map(|o| o.contact_name.clone()),
+            ),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                payload[key] = json!(value);
+            }
+        }
+        if let (true, Some(counterparty)) =
+            (recipient.address.is_saved_door(), &recipient.counterparty)
+        {
+            // The saved address belongs to a stored recipient, so the document refers
+            // to it by refs instead of describing a new one.
+            let properties = payload.as_object_mut().expect("payload is an object");
+            properties.remove("NewAddress");
+            properties.insert("CityRecipient".to_string(), json!(counterparty.city_id));
+            properties.insert("Recipient".to_string(), json!(counterparty.counterparty_id));
+            properties.insert(
+                "ContactRecipient".to_string(),
+                json!(counterparty.contact_id),
+            );
+        }
+        self.run("InternetDocument", "save", payload).await
+    }
+
+    /// Saves a street address for a counterparty, [`NovaPoshta::door_address`] reuses
+    /// one that is already saved.
+    pub async fn create_address(
+        &self,
+        counterparty_ref: &str,
+        street_ref: &str,
+        house: &str,
+        flat: Option<&str>,
+    ) -> Result<NovaResponse<NovaCounterpartyAddress>> {
+        let mut payload = json!({
+            "CounterpartyRef": counterparty_ref,
+            "StreetRef": street_ref,
+            "BuildingNumber": house
+        });
+        if let Some(flat) = flat {
+            payload["Flat"] = json!(flat);
+        }
+        self.run("Address", "save", payload).await
+    }
+
+    /// Ref of the counterparty's saved address at this street, house and flat, the
+    /// address is saved only when there is no such one yet.
+    pub async fn door_address(
+        &self,
+        counterparty_ref: &str,
+        property: NovaCounterpartyProperty,
+        street_ref: &str,
+        house: &str,
+        flat: Option<&str>,
+    ) -> Result<String> {
+        let flat = flat.unwrap_or_default().trim();
+        let saved = self
+            .get_counterparty_addresses(counterparty_ref, property)
+            .await?
+            .data
+            .into_iter()
+            .find(|a| {
+                a.street_ref == street_ref
+                    && a.building_description.trim() == house.trim()
+                    && a.flat.trim() == flat
+            });
+        if let Some(address) = saved {
+            return Ok(address.id);
+        }
+        self.create_address(
+            counterparty_ref,
+            street_ref,
+            house,
+            Some(flat).filter(|f| !f.is_empty()),
+        )
+        .await?
+        .data
+        .into_iter()
+        .next()
+        .map(|a| a.id)
+        .ok_or_else(|| {
+            NovaError::Api(Box::new(NovaApiError {
+                errors: vec!["Address.save returned no address".to_string()],
+                ..Default::default()
+            }))
+        })
+    }
+
+    /// Warehouse by its ref, or by its number in a city as shown on its sign.
+    pub async fn find_warehouse(&self, city_name: &str, id: &WarehouseId) -> Result<NovaWarehouse> {
+        self.lookup_warehouse(city_name, id, "warehouse", |_| true)
+            .await
+    }
+
+    /// Postomat by its ref, or by its number in a city as printed on the locker.
+    pub async fn find_postomat(&self, city_name: &str, id: &WarehouseId) -> Result<NovaWarehouse> {
+        self.lookup_warehouse(city_name, id, "postomat", |w| {
+            w.kind() == NovaWarehouseKind::Postomat
+        })
+        .await
+    }
+
+    async fn lookup_warehouse(
+        &self,
+        city_name: &str,
+        id: &WarehouseId,
+        kind: &str,
+        accept: impl Fn(&NovaWarehouse) -> bool,
+    ) -> Result<NovaWarehouse> {
+        let query = match id {
+            WarehouseId::Ref(id) => WarehouseQuery::id(id),
+            WarehouseId::Number(number) => WarehouseQuery::default()
+                .city_name(city_name)
+                .number(number),
+        };
+        self.get_warehouses(query)
+            .await?
+            .data
+            .into_iter()
+            .find(|w| {
+                accept(w)
+                    && match id {
+                        WarehouseId::Ref(id) => &w.id == id,
+                        WarehouseId::Number(number) => &w.number == number,
+                    }
+            })
+            .ok_or_else(|| {
+                ValidationError::new(
+                    format!("recipient.address.{}", kind),
+                    format!("{} {:?} not found in {}", kind, id, city_name),
+                )
+                .into()
+            })
     }
 
     pub async fn delete_shipments(
         &self,
         document_ids: Vec<String>,
-    ) -> Result<NovaResponse<NovaShipmentDelete>, Box<dyn std::error::Error>> {
-        Ok(self
-            .run(
-                "InternetDocument",
-                "delete",
-                json!({ "DocumentRefs": document_ids }),
-            )
-            .await?)
+    ) -> Result<NovaResponse<NovaShipmentDelete>> {
+        self.run(
+            "InternetDocument",
+            "delete",
+            json!({ "DocumentRefs": document_ids }),
+        )
+        .await
     }
 
     pub async fn estimate_shipment_price(
@@ -173,13 +836,15 @@ impl NovaPoshta {
         city_ref1: String,
         service_type: NovaServiceType,
         cargos: Vec<Cargo>,
-    ) -> Result<NovaResponse<NovaDocumentPrice>, Box<dyn std::error::Error>> {
+    ) -> Result<NovaResponse<NovaDocumentPrice>> {
+        cargos.validate()?;
         let seats_amount = cargos.len();
         let (weight, price, to_payment, _) = cargos.into_ttn_values();
+        let options_seat = cargos.options_seat();
 
-        let redelivery_calculate = if to_payment > 0 {
+        let redelivery_calculate = if !to_payment.is_zero() {
             json!({
-                "CargoType": "Money",
+                "CargoType": NovaCargoType::Money,
                 "Amount": to_payment
             })
         } else {
@@ -188,23 +853,24 @@ impl NovaPoshta {
 
         let now = chrono::Local::now() + Duration::weeks(1);
 
-        Ok(self
-            .run(
-                "InternetDocument",
-                "getDocumentPrice",
-                json!({
-                    "CitySender": city_ref0,
-                    "CityRecipient": city_ref1,
-                    "Weight": weight,
-                    "ServiceType": service_type,
-                    "Cost": price,
-                    "CargoType": "Parcel",
-                    "SeatsAmount": seats_amount,
-                    "RedeliveryCalculate": redelivery_calculate,
-                    "DateTime": now.into_ttn_time()
-                }),
-            )
-            .await?)
+        self.run(
+            "InternetDocument",
+            "getDocumentPrice",
+            json!({
+                "CitySender": city_ref0,
+                "CityRecipient": city_ref1,
+                "Weight": weight,
+                "ServiceType": service_type,
+                "Cost": price,
+                "CargoType": cargos.cargo_type(),
+                "CargoDetails": cargos.cargo_details(),
+                "SeatsAmount": seats_amount,
+                "OptionsSeat": options_seat,
+                "RedeliveryCalculate": redelivery_calculate,
+                "DateTime": now.into_ttn_time()
+            }),
+        )
+        .await
     }
 
     pub async fn estimate_shipment_date(
@@ -213,68 +879,848 @@ impl NovaPoshta {
         service_type: NovaServiceType,
         city_sender_ref: String,
         city_recipient_ref: String,
-    ) -> Result<NovaResponse<NovaDeliveryDate>, Box<dyn std::error::Error>> {
-        Ok(self
-            .run(
-                "InternetDocument",
-                "getDocumentDeliveryDate",
-                json!({
-                    "DateTime": send_date,
-                    "ServiceType": service_type,
-                    "CitySender": city_sender_ref,
-                    "CityRecipient": city_recipient_ref
-                }),
-            )
-            .await?)
+    ) -> Result<NovaResponse<NovaDeliveryDate>> {
+        self.run(
+            "InternetDocument",
+            "getDocumentDeliveryDate",
+            json!({
+                "DateTime": send_date,
+                "ServiceType": service_type,
+                "CitySender": city_sender_ref,
+                "CityRecipient": city_recipient_ref
+            }),
+        )
+        .await
     }
 
     pub async fn shipments_statuses(
         &self,
         documents: Vec<NovaStatusFetch>,
-    ) -> Result<NovaResponse<NovaShipment>, Box<dyn std::error::Error>> {
-        Ok(self
-            .run(
-                "TrackingDocument",
-                "getStatusDocuments",
-                json!({ "Documents": documents }),
-            )
-            .await?)
+    ) -> Result<NovaResponse<NovaShipment>> {
+        self.run(
+            "TrackingDocument",
+            "getStatusDocuments",
+            json!({ "Documents": documents }),
+        )
+        .await
     }
 }
 
+/// `getCounterparties` filter, `FindByString` is left out instead of sent as null.
+fn counterparties_query(
+    property: NovaCounterpartyProperty,
+    find_by_string: Option<&str>,
+) -> serde_json::Value {
+    let mut query = json!({ "CounterpartyProperty": property });
+    if let Some(find_by_string) = find_by_string {
+        query["FindByString"] = json!(find_by_string);
+    }
+    query
+}
+
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct NovaResponse<T> {
     pub success: bool,
+    #[serde(default = "Vec::new")]
     pub data: Vec<T>,
-    pub errors: Vec<serde_json::Value>,
-    pub warnings: Vec<serde_json::Value>,
+    #[serde(default, deserialize_with = "de::messages")]
+    pub errors: Vec<String>,
+    #[serde(default, deserialize_with = "de::messages")]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub info: serde_json::Value,
+    #[serde(default, deserialize_with = "de::codes")]
+    pub error_codes: Vec<u64>,
+    #[serde(default, deserialize_with = "de::codes")]
+    pub warning_codes: Vec<u64>,
+    #[serde(default, deserialize_with = "de::codes")]
+    pub info_codes: Vec<u64>,
+}
+
+impl<T> NovaResponse<T> {
+    /// Api error described by this envelope, `None` when the request succeeded.
+    pub fn error(&self) -> Option<NovaApiError> {
+        if self.success {
+            return None;
+        }
+        Some(NovaApiError {
+            errors: self.errors.clone(),
+            warnings: self.warnings.clone(),
+            error_codes: self.error_codes.clone(),
+            warning_codes: self.warning_codes.clone(),
+            info: self.info.clone(),
+        })
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "PascalCase")]
 pub struct NovaWarehouse {
     pub description: String,
+    #[serde(default)]
+    pub description_ru: String,
     #[serde(rename = "Ref")]
     pub id: String,
     pub short_address: String,
+    #[serde(default)]
     pub phone: String,
     pub number: String,
+    #[serde(default)]
+    pub warehouse_index: String,
+    #[serde(rename = "TypeOfWarehouse", default)]
+    pub type_of_warehouse_ref: String,
+    #[serde(default)]
+    pub category_of_warehouse: String,
     pub city_ref: String,
     pub city_description: String,
-    pub longitude: String,
-    pub latitude: String,
-    pub place_max_weight_allowed: String,
-    pub deny_to_select: String,
+    #[serde(default)]
+    pub settlement_ref: String,
+    #[serde(default, deserialize_with = "de::number")]
+    pub longitude: f64,
+    #[serde(default, deserialize_with = "de::number")]
+    pub latitude: f64,
+    /// Max weight of one seat in kilograms, zero means no limit.
+    #[serde(default, deserialize_with = "de::number")]
+    pub place_max_weight_allowed: f64,
+    /// Max weight of the whole shipment in kilograms, zero means no limit.
+    #[serde(default, deserialize_with = "de::number")]
+    pub total_max_weight_allowed: f64,
+    #[serde(default, deserialize_with = "de::object_or_default")]
+    pub sending_limitations_on_dimensions: NovaDimensionsLimit,
+    #[serde(default, deserialize_with = "de::object_or_default")]
+    pub receiving_limitations_on_dimensions: NovaDimensionsLimit,
+    #[serde(default)]
+    pub schedule: NovaSchedule,
+    #[serde(default)]
+    pub reception: NovaSchedule,
+    #[serde(default)]
+    pub delivery: NovaSchedule,
+    #[serde(default)]
+    pub warehouse_status: NovaWarehouseStatus,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub post_finance: bool,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub bicycle_parking: bool,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub deny_to_select: bool,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct NovaCounterparty {
-    #[serde(rename = "Ref")]
-    pub id: String,
-    pub first_name: String,
-    pub last_name: String,
-    pub middle_name: String,
-    pub phones: String,
-    #[serde(rename = "Description")]
-    pub full_name: String,
+impl NovaWarehouse {
+    pub fn kind(&self) -> NovaWarehouseKind {
+        NovaWarehouseKind::from_refs(&self.type_of_warehouse_ref, &self.category_of_warehouse)
+    }
+
+    /// Rejects seats that do not fit the warehouse, postomats without reported limits
+    /// are checked against the largest cell.
+    pub fn check_cargos(&self, cargos: &[Cargo]) -> Result<(), ValidationError> {
+        let postomat = self.kind() == NovaWarehouseKind::Postomat;
+        let mut place_limit = self.place_max_weight_allowed;
+        let mut dimensions = self.receiving_limitations_on_dimensions;
+        if postomat && place_limit <= 0.0 {
+            place_limit = POSTOMAT_MAX_WEIGHT;
+        }
+        if postomat && dimensions.is_unlimited() {
+            dimensions = POSTOMAT_MAX_DIMENSIONS;
+        }
+        let mut errors = ValidationError::default();
+        for (i, cargo) in cargos.iter().enumerate() {
+            let seat = &cargo.options_seat;
+            if place_limit > 0.0 && seat.weight as f64 > place_limit {
+                errors.push(
+                    format!("cargos[{}].options_seat.weight", i),
+                    format!(
+                        "{} kg, warehouse {} accepts up to {} kg",
+                        seat.weight, self.number, place_limit
+                    ),
+                );
+            }
+            if !dimensions.fits(seat.Width as f64, seat.Height as f64, seat.Length as f64) {
+                errors.push(
+                    format!("cargos[{}].options_seat", i),
+                    format!(
+                        "{}x{}x{} cm does not fit warehouse {} limit of {}x{}x{} cm",
+                        seat.Width,
+                        seat.Length,
+                        seat.Height,
+                        self.number,
+                        dimensions.width,
+                        dimensions.length,
+                        dimensions.height
+                    ),
+                );
+            }
+        }
+        let total: f64 = cargos.iter().map(|c| c.options_seat.weight as f64).sum();
+        if self.total_max_weight_allowed > 0.0 && total > self.total_max_weight_allowed {
+            errors.push(
+                "cargos",
+                format!(
+                    "shipment weighs {} kg, warehouse {} accepts up to {} kg",
+                    total, self.number, self.total_max_weight_allowed
+                ),
+            );
+        }
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_api, mock_responses, success};
+    use serde_json::Value;
+
+    fn city(id: u32, area: &str) -> Value {
+        json!({
+            "Ref": id.to_string(),
+            "Description": format!("city {}", id),
+            "Area": area,
+            "SettlementType": "563ced10-f210-11e3-8c4a-0050568002cf"
+        })
+    }
+
+    #[tokio::test]
+    async fn area_filter_does_not_end_city_pages() {
+        let (nova, requests) =
+            mock_api(
+                |request| match request["methodProperties"]["Page"].as_u64().unwrap() {
+                    1 => json!([city(1, "other"), city(2, "other")]),
+                    2 => json!([city(3, "kyiv"), city(4, "other")]),
+                    _ => json!([city(5, "kyiv")]),
+                },
+            )
+            .await;
+        let cities: Vec<NovaCity> = nova
+            .cities_stream(CityQuery::default().area("kyiv").limit(2))
+            .try_collect()
+            .await
+            .unwrap();
+        let ids: Vec<&str> = cities.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["3", "5"]);
+        let pages: Vec<Value> = requests
+            .calls("Address", "getCities")
+            .into_iter()
+            .map(|p| json!([p["Page"], p["Limit"]]))
+            .collect();
+        assert_eq!(pages, [json!([1, 2]), json!([2, 2]), json!([3, 2])]);
+    }
+
+    #[tokio::test]
+    async fn warehouse_pages_keep_the_query() {
+        let (nova, requests) = mock_api(|request| {
+            let properties = &request["methodProperties"];
+            let limit = properties["Limit"].as_u64().unwrap() as usize;
+            match properties["Page"].as_u64().unwrap() {
+                3 => json!([postomat()]),
+                _ => json!(vec![postomat(); limit.min(2)]),
+            }
+        })
+        .await;
+        let query = WarehouseQuery::default()
+            .city_ref("kyiv")
+            .post_finance(true)
+            .page(2)
+            .limit(2);
+        let warehouses: Vec<NovaWarehouse> =
+            nova.warehouses_stream(query).try_collect().await.unwrap();
+        assert_eq!(warehouses.len(), 3);
+        let _: Vec<NovaWarehouse> = nova
+            .warehouses_stream(WarehouseQuery::find("Київ").limit(0))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            requests.calls("AddressGeneral", "getWarehouses"),
+            [
+                json!({ "CityRef": "kyiv", "PostFinance": "1", "Page": 2, "Limit": 2 }),
+                json!({ "CityRef": "kyiv", "PostFinance": "1", "Page": 3, "Limit": 2 }),
+                json!({ "FindByString": "Київ", "Page": 1, "Limit": WAREHOUSES_PAGE_LIMIT }),
+            ]
+        );
+    }
+
+    /// Answers the first request with `failure`, every later one with an empty success.
+    fn fails_once(failure: (u16, Value)) -> impl Fn(&Value) -> (u16, Value) {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        move |_| match calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => failure.clone(),
+            _ => (200, success(json!([]))),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_are_retried_and_saves_are_not() {
+        let (nova, requests) = mock_responses(fails_once((503, json!({})))).await;
+        nova.call_raw::<Value>("Common", "getCargoTypes", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(requests.calls("Common", "getCargoTypes").len(), 2);
+
+        let (nova, requests) = mock_responses(fails_once((503, json!({})))).await;
+        let error = nova
+            .call_raw::<Value>("InternetDocument", "save", json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, NovaError::Status { .. }), "{}", error);
+        assert_eq!(requests.calls("InternetDocument", "save").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn throttled_reads_are_retried() {
+        let throttled = json!({
+            "success": false,
+            "data": [],
+            "errors": ["To many requests"]
+        });
+        let (nova, requests) = mock_responses(fails_once((200, throttled))).await;
+        nova.get_cities(CityQuery::name("Київ")).await.unwrap();
+        assert_eq!(requests.calls("Address", "getCities").len(), 2);
+    }
+
+    fn failed() -> Value {
+        json!({
+            "success": false,
+            "data": [],
+            "errors": {"20000200068": "Document number is not correct"},
+            "warnings": [],
+            "info": {"totalCount": 0},
+            "errorCodes": ["20000200068"],
+            "warningCodes": [],
+            "infoCodes": []
+        })
+    }
+
+    #[tokio::test]
+    async fn unsuccessful_calls_become_api_errors() {
+        let (nova, _) = mock_responses(|_| (200, failed())).await;
+        let error = nova.get_cities(CityQuery::name("Київ")).await.unwrap_err();
+        let NovaError::Api(error) = error else {
+            panic!("expected an api error, got {}", error);
+        };
+        assert_eq!(error.errors, ["Document number is not correct"]);
+        assert_eq!(error.error_codes, [20000200068]);
+        assert_eq!(error.info, json!({"totalCount": 0}));
+    }
+
+    #[tokio::test]
+    async fn raw_calls_return_failed_envelopes() {
+        let (nova, requests) = mock_responses(|_| (200, failed())).await;
+        let response = nova
+            .call_raw::<Value>("Address", "getCities", json!({}))
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(response.errors, ["Document number is not correct"]);
+        assert_eq!(response.error_codes, [20000200068]);
+        assert_eq!(requests.all().len(), 1);
+    }
+
+    fn answer(request: &Value) -> Value {
+        match (
+            request["modelName"].as_str().unwrap(),
+            request["calledMethod"].as_str().unwrap(),
+        ) {
+            ("Counterparty", "getCounterpartyAddresses") => json!([]),
+            ("Address", "save") => json!([{ "Ref": "saved-address", "Description": "Хрещатик 1" }]),
+            ("InternetDocument", "save") => json!([{
+                "Ref": "document",
+                "IntDocNumber": "20400000000000",
+                "EstimatedDeliveryDate": "20.10.2026"
+            }]),
+            other => panic!("unexpected call {:?}", other),
+        }
+    }
+
+    fn shipment() -> (SenderContact, Vec<Cargo>) {
+        let sender = SenderContact::new(
+            "sender-city",
+            "sender-warehouse",
+            "sender-contact",
+            "sender",
+            "380991234567",
+        );
+        let cargo = Cargo::new(
+            100,
+            NovaOptionsSeat::from_dimensions(20, 30, 10, 1.0),
+            false,
+            "box".to_string(),
+        );
+        (sender, vec![cargo])
+    }
+
+    #[tokio::test]
+    async fn door_delivery_sends_saved_address() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::street("street", "1".to_string(), "5".to_string()),
+        )
+        .counterparty("recipient-city", "recipient", "recipient-contact");
+        nova.new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap();
+
+        let saved = requests.calls("Address", "save");
+        assert_eq!(
+            saved,
+            [json!({
+                "CounterpartyRef": "recipient",
+                "StreetRef": "street",
+                "BuildingNumber": "1",
+                "Flat": "5"
+            })]
+        );
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["RecipientAddress"], "saved-address");
+        assert_eq!(document["Recipient"], "recipient");
+        assert_eq!(document["ContactRecipient"], "recipient-contact");
+        assert_eq!(document["CityRecipient"], "recipient-city");
+        assert!(document.get("RecipientAddressName").is_none());
+        assert!(document.get("NewAddress").is_none());
+    }
+
+    #[tokio::test]
+    async fn door_delivery_reuses_matching_recipient_address() {
+        let (nova, requests) = mock_api(|request| match request["calledMethod"].as_str() {
+            Some("getCounterpartyAddresses") => json!([
+                { "Ref": "other-house", "StreetRef": "street", "BuildingDescription": "2", "Flat": "5" },
+                { "Ref": "home", "StreetRef": "street", "BuildingDescription": "1", "Flat": "5" }
+            ]),
+            _ => answer(request),
+        })
+        .await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::street("street", " 1".to_string(), "5".to_string()),
+        )
+        .counterparty("recipient-city", "recipient", "recipient-contact");
+        nova.new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            requests.calls("Counterparty", "getCounterpartyAddresses"),
+            [json!({ "Ref": "recipient", "CounterpartyProperty": "Recipient" })]
+        );
+        assert!(requests.calls("Address", "save").is_empty());
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["RecipientAddress"], "home");
+    }
+
+    #[tokio::test]
+    async fn saved_door_delivery_looks_nothing_up() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::saved_door("home"),
+        )
+        .counterparty("recipient-city", "recipient", "recipient-contact");
+        nova.new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap();
+
+        let calls = requests.all();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["calledMethod"], "save");
+        assert_eq!(calls[0]["modelName"], "InternetDocument");
+        let document = &calls[0]["methodProperties"];
+        assert_eq!(document["RecipientAddress"], "home");
+        assert_eq!(document["Recipient"], "recipient");
+        assert_eq!(document["ContactRecipient"], "recipient-contact");
+        assert_eq!(document["CityRecipient"], "recipient-city");
+        assert_eq!(
+            document["ServiceType"],
+            json!(NovaServiceType::WarehouseDoors)
+        );
+        assert!(document.get("NewAddress").is_none());
+    }
+
+    #[tokio::test]
+    async fn door_delivery_by_street_name_describes_new_address() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::address("Хрещатик".to_string(), "1".to_string(), "5".to_string()),
+        );
+        nova.new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap();
+
+        assert!(requests.calls("Address", "save").is_empty());
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["NewAddress"], "1");
+        assert_eq!(document["RecipientAddressName"], "Хрещатик");
+        assert_eq!(document["RecipientHouse"], "1");
+        assert_eq!(document["RecipientFlat"], "5");
+        assert!(document.get("RecipientAddress").is_none());
+    }
+
+    #[tokio::test]
+    async fn warehouse_ref_is_sent_with_its_number() {
+        let (nova, requests) = mock_api(|request| match request["modelName"].as_str() {
+            Some("AddressGeneral") => json!([{
+                "Ref": "warehouse",
+                "Description": "Відділення №12",
+                "ShortAddress": "Київ, Хрещатик, 1",
+                "Number": "12",
+                "CityRef": "kyiv",
+                "CityDescription": "Київ"
+            }]),
+            _ => answer(request),
+        })
+        .await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::warehouse_ref("warehouse"),
+        );
+        nova.new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            requests.calls("AddressGeneral", "getWarehouses")[0]["Ref"],
+            "warehouse"
+        );
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["NewAddress"], "1");
+        assert_eq!(document["RecipientAddressName"], "12");
+        assert_eq!(document["RecipientAddress"], "warehouse");
+    }
+
+    #[tokio::test]
+    async fn missing_recipient_fields_are_not_sent() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let person = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::warehouse(12),
+        );
+        nova.new_shipment(sender.clone(), person, cargos.clone(), None)
+            .await
+            .unwrap();
+        let company = Recipient::new(
+            "Київ".to_string(),
+            "ТОВ Кобзар".to_string(),
+            "380671234567",
+            false,
+            Address::warehouse(12),
+        )
+        .organization("12345678", "tov", "Шевченко Тарас")
+        .payer(NovaPayer::ThirdPerson("payer".to_string()));
+        nova.new_shipment(sender, company, cargos, None)
+            .await
+            .unwrap();
+
+        let documents = requests.calls("InternetDocument", "save");
+        for document in &documents {
+            let nulls: Vec<_> = document
+                .as_object()
+                .unwrap()
+                .iter()
+                .filter(|(_, value)| value.is_null())
+                .map(|(key, _)| key)
+                .collect();
+            assert!(nulls.is_empty(), "{:?}", nulls);
+        }
+        for key in [
+            "ThirdPerson",
+            "EDRPOU",
+            "OwnershipForm",
+            "RecipientContactName",
+            "RecipientAddress",
+            "RecipientHouse",
+            "RecipientFlat",
+            "RecipientArea",
+            "SettlementType",
+        ] {
+            assert!(documents[0].get(key).is_none(), "{}", key);
+        }
+        assert_eq!(documents[0]["RecipientAddressName"], "12");
+        assert_eq!(documents[1]["ThirdPerson"], "payer");
+        assert_eq!(documents[1]["EDRPOU"], "12345678");
+        assert_eq!(documents[1]["OwnershipForm"], "tov");
+        assert_eq!(documents[1]["RecipientContactName"], "Шевченко Тарас");
+    }
+
+    #[tokio::test]
+    async fn door_pickup_uses_saved_sender_address() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::warehouse(12),
+        );
+        nova.new_shipment(sender.saved_door("sender-address"), recipient, cargos, None)
+            .await
+            .unwrap();
+
+        assert_eq!(requests.all().len(), 1);
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["SenderAddress"], "sender-address");
+        assert_eq!(document["ServiceType"], "DoorsWarehouse");
+    }
+
+    #[tokio::test]
+    async fn door_pickup_reuses_matching_sender_address() {
+        let (nova, requests) = mock_api(|request| match request["calledMethod"].as_str() {
+            Some("getCounterpartyAddresses") => json!([
+                { "Ref": "other-flat", "StreetRef": "street", "BuildingDescription": "1", "Flat": "5" },
+                { "Ref": "office", "StreetRef": "street", "BuildingDescription": "1" }
+            ]),
+            _ => answer(request),
+        })
+        .await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::warehouse(12),
+        );
+        let date = chrono::Local::now();
+        nova.new_shipment(
+            sender.door("street", "1", None),
+            recipient,
+            cargos,
+            Some(date),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            requests.calls("Counterparty", "getCounterpartyAddresses"),
+            [json!({ "Ref": "sender", "CounterpartyProperty": "Sender" })]
+        );
+        assert!(requests.calls("Address", "save").is_empty());
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["SenderAddress"], "office");
+        assert_eq!(document["DateTime"], date.into_ttn_time());
+    }
+
+    #[tokio::test]
+    async fn counterparty_search_omits_missing_filter() {
+        let (nova, requests) = mock_api(|_| json!([])).await;
+        nova.search_counterparties(NovaCounterpartyProperty::Recipient, None, 2)
+            .await
+            .unwrap();
+        let _: Vec<NovaCounterparty> = nova
+            .counterparties_stream(NovaCounterpartyProperty::Sender, Some("Шевч".to_string()))
+            .try_collect()
+            .await
+            .unwrap();
+
+        let calls = requests.calls("Counterparty", "getCounterparties");
+        assert_eq!(
+            calls,
+            [
+                json!({ "CounterpartyProperty": "Recipient", "Page": 2 }),
+                json!({
+                    "CounterpartyProperty": "Sender",
+                    "FindByString": "Шевч",
+                    "Page": 1,
+                    "Limit": COUNTERPARTIES_PAGE_LIMIT
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn contact_person_payloads_name_the_counterparty() {
+        let (nova, requests) = mock_api(|_| json!([{ "Ref": "contact" }])).await;
+        let person = ContactPerson::new("Тарас", "Шевченко", "380671234567").email("t@example.com");
+        let created = nova
+            .create_contact_person("counterparty", &person)
+            .await
+            .unwrap();
+        assert_eq!(created.data[0].id, "contact");
+        nova.update_contact_person("counterparty", "contact", &person)
+            .await
+            .unwrap();
+        let err = nova
+            .create_contact_person("counterparty", &ContactPerson::new("", "Шевченко", "1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, NovaError::Validation(_)));
+
+        let person = json!({
+            "FirstName": "Тарас",
+            "LastName": "Шевченко",
+            "Phone": "380671234567",
+            "Email": "t@example.com",
+            "CounterpartyRef": "counterparty"
+        });
+        assert_eq!(
+            requests.calls("ContactPerson", "save"),
+            std::slice::from_ref(&person)
+        );
+        let mut updated = person;
+        updated["Ref"] = json!("contact");
+        assert_eq!(requests.calls("ContactPerson", "update"), [updated]);
+        assert_eq!(requests.all().len(), 2);
+    }
+
+    fn postomat() -> Value {
+        json!({
+            "Ref": "postomat",
+            "Description": "Поштомат №5590",
+            "ShortAddress": "Київ, Хрещатик, 22",
+            "Number": "5590",
+            "WarehouseIndex": "11/22",
+            "CityRef": "kyiv",
+            "CityDescription": "Київ",
+            "TypeOfWarehouse": TYPE_POSTOMAT_REF,
+            "PlaceMaxWeightAllowed": "",
+            "ReceivingLimitationsOnDimensions": []
+        })
+    }
+
+    fn seat(width: i32, length: i32, height: i32, weight: f32) -> Cargo {
+        Cargo::new(
+            100,
+            NovaOptionsSeat::from_dimensions(width, length, height, weight),
+            false,
+            "box".to_string(),
+        )
+    }
+
+    fn fields(result: std::result::Result<(), ValidationError>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(e) => e.fields.into_iter().map(|f| f.field).collect(),
+        }
+    }
+
+    #[test]
+    fn postomats_without_limits_use_the_largest_cell() {
+        let postomat: NovaWarehouse = serde_json::from_value(postomat()).unwrap();
+        assert!(fields(postomat.check_cargos(&[seat(40, 60, 30, 20.0)])).is_empty());
+        assert_eq!(
+            fields(postomat.check_cargos(&[seat(10, 10, 10, 1.0), seat(20, 70, 10, 21.0)])),
+            ["cargos[1].options_seat.weight", "cargos[1].options_seat"]
+        );
+
+        let mut branch = postomat.clone();
+        branch.type_of_warehouse_ref = TYPE_BRANCH_REF.to_string();
+        assert!(fields(branch.check_cargos(&[seat(100, 100, 100, 200.0)])).is_empty());
+
+        branch.place_max_weight_allowed = 30.0;
+        branch.total_max_weight_allowed = 50.0;
+        branch.receiving_limitations_on_dimensions = NovaDimensionsLimit {
+            width: 50.0,
+            height: 50.0,
+            length: 50.0,
+        };
+        let seats = [
+            seat(50, 50, 50, 30.0),
+            seat(10, 10, 10, 31.0),
+            seat(60, 10, 10, 1.0),
+        ];
+        assert_eq!(
+            fields(branch.check_cargos(&seats)),
+            [
+                "cargos[1].options_seat.weight",
+                "cargos[2].options_seat",
+                "cargos"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn postomat_shipment_sends_its_index() {
+        let (nova, requests) = mock_api(|request| match request["calledMethod"].as_str() {
+            Some("getWarehouses") => json!([postomat()]),
+            _ => answer(request),
+        })
+        .await;
+        let (sender, cargos) = shipment();
+        let recipient = || {
+            Recipient::new(
+                "Київ".to_string(),
+                "Шевченко Тарас".to_string(),
+                "380671234567",
+                false,
+                Address::pochtomat(5590),
+            )
+        };
+
+        let error = nova
+            .new_shipment(
+                sender.clone(),
+                recipient(),
+                vec![seat(20, 70, 10, 1.0)],
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("cargos[0].options_seat"),
+            "{}",
+            error
+        );
+        assert!(requests.calls("InternetDocument", "save").is_empty());
+
+        nova.new_shipment(sender, recipient(), cargos, None)
+            .await
+            .unwrap();
+        let query = &requests.calls("AddressGeneral", "getWarehouses")[0];
+        assert_eq!(query["CityName"], "Київ");
+        assert_eq!(query["WarehouseId"], "5590");
+        let document = &requests.calls("InternetDocument", "save")[0];
+        assert_eq!(document["ServiceType"], "WarehousePostomat");
+        assert_eq!(document["RecipientAddressName"], "5590");
+        assert_eq!(document["RecipientAddress"], "postomat");
+        assert_eq!(document["RecipientWarehouseIndex"], "11/22");
+    }
+
+    #[tokio::test]
+    async fn door_delivery_needs_stored_recipient() {
+        let (nova, requests) = mock_api(answer).await;
+        let (sender, cargos) = shipment();
+        let recipient = Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            Address::street("street", "1".to_string(), String::new()),
+        );
+        let error = nova
+            .new_shipment(sender, recipient, cargos, None)
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("recipient.counterparty"),
+            "{}",
+            error
+        );
+        assert!(requests.all().is_empty());
+    }
 }
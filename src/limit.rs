@@ -0,0 +1,173 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Token bucket plus an optional cap on requests in flight.
+///
+/// One limiter is meant to be shared by every client using the same api key, either
+/// through clones of one [`crate::NovaPoshta`] or by passing the same `Arc` to
+/// several builders.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    capacity: f64,
+    bucket: Mutex<Bucket>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Held while a request is in flight.
+#[derive(Debug)]
+pub struct RatePermit {
+    _in_flight: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// Allows `requests` per `period`, starting with a full bucket of the same size.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        let requests = requests.max(1) as f64;
+        RateLimiter {
+            rate: requests / period.as_secs_f64(),
+            capacity: requests,
+            bucket: Mutex::new(Bucket {
+                tokens: requests,
+                updated: Instant::now(),
+            }),
+            in_flight: None,
+        }
+    }
+
+    /// Number of requests that may be sent back to back after an idle period.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.capacity = burst.max(1) as f64;
+        self.bucket.get_mut().unwrap().tokens = self.capacity;
+        self
+    }
+
+    pub fn max_in_flight(mut self, requests: usize) -> Self {
+        self.in_flight = Some(Arc::new(Semaphore::new(requests.max(1))));
+        self
+    }
+
+    /// Waits for a free slot and a token.
+    pub async fn acquire(&self) -> RatePermit {
+        let in_flight = match &self.in_flight {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("rate limiter semaphore is never closed"),
+            ),
+            None => None,
+        };
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        RatePermit {
+            _in_flight: in_flight,
+        }
+    }
+
+    /// Takes a token, going into debt when the bucket is empty, and returns how long
+    /// the caller has to wait for the debt to be paid off.
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.capacity) - 1.0;
+        bucket.updated = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{serve, success};
+    use crate::NovaPoshta;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn bucket_spaces_out_requests() {
+        let limiter = RateLimiter::new(10, Duration::from_secs(1)).burst(2);
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        // Two tokens from the burst, the other three arrive every 100ms.
+        assert_eq!(start.elapsed(), Duration::from_millis(300));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bucket_refills_while_idle() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(1));
+        limiter.acquire().await;
+        limiter.acquire().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+
+    /// Answers every request with an empty successful envelope after `delay`,
+    /// recording the highest number of requests handled at the same time.
+    async fn mock_server(delay: Duration) -> (String, Arc<AtomicUsize>) {
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let recorded = peak.clone();
+        let (url, _) = serve(move |_| {
+            let current = current.clone();
+            let peak = peak.clone();
+            async move {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                current.fetch_sub(1, Ordering::SeqCst);
+                (200, success(json!([])))
+            }
+        })
+        .await;
+        (url, recorded)
+    }
+
+    #[tokio::test]
+    async fn client_caps_requests_in_flight() {
+        let (url, peak) = mock_server(Duration::from_millis(50)).await;
+        let nova = NovaPoshta::builder("key")
+            .base_url(url)
+            .rate_limiter(RateLimiter::new(1000, Duration::from_secs(1)).max_in_flight(2))
+            .build()
+            .unwrap();
+
+        let calls = (0..8).map(|_| {
+            let nova = nova.clone();
+            tokio::spawn(async move {
+                nova.call_raw::<serde_json::Value>("Common", "getCargoTypes", json!({}))
+                    .await
+                    .unwrap();
+            })
+        });
+        for call in calls.collect::<Vec<_>>() {
+            call.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,238 @@
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// Page size used when walking every page of cities.
+pub const CITIES_PAGE_LIMIT: u32 = 500;
+/// The api caps `getSettlements` pages at 150 items.
+pub const SETTLEMENTS_PAGE_LIMIT: u32 = 150;
+
+/// Filter for [`crate::NovaPoshta::get_cities`].
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct CityQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub find_by_string: Option<String>,
+    #[serde(rename = "Ref", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The api has no area filter, cities of other areas are dropped from each page.
+    #[serde(skip)]
+    pub area_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl CityQuery {
+    // Name or its beginning, like Київ or Льв
+    pub fn name(name: &str) -> Self {
+        CityQuery {
+            find_by_string: Some(name.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    pub fn id(city_ref: &str) -> Self {
+        CityQuery {
+            id: Some(city_ref.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    pub fn area(mut self, area_ref: &str) -> Self {
+        self.area_id = Some(area_ref.to_owned());
+        self
+    }
+
+    /// Pages start at 1.
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaCity {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    pub description: String,
+    #[serde(default)]
+    pub description_ru: String,
+    #[serde(rename = "CityID", default)]
+    pub city_id: String,
+    #[serde(rename = "Area")]
+    pub area_id: String,
+    #[serde(default)]
+    pub area_description: String,
+    #[serde(default)]
+    pub area_description_ru: String,
+    #[serde(rename = "SettlementType")]
+    pub settlement_type_id: String,
+    #[serde(default)]
+    pub settlement_type_description: String,
+    #[serde(default)]
+    pub settlement_type_description_ru: String,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub is_branch: bool,
+    #[serde(rename = "Delivery1", default, deserialize_with = "de::flag")]
+    pub delivery_monday: bool,
+    #[serde(rename = "Delivery2", default, deserialize_with = "de::flag")]
+    pub delivery_tuesday: bool,
+    #[serde(rename = "Delivery3", default, deserialize_with = "de::flag")]
+    pub delivery_wednesday: bool,
+    #[serde(rename = "Delivery4", default, deserialize_with = "de::flag")]
+    pub delivery_thursday: bool,
+    #[serde(rename = "Delivery5", default, deserialize_with = "de::flag")]
+    pub delivery_friday: bool,
+    #[serde(rename = "Delivery6", default, deserialize_with = "de::flag")]
+    pub delivery_saturday: bool,
+    #[serde(rename = "Delivery7", default, deserialize_with = "de::flag")]
+    pub delivery_sunday: bool,
+}
+
+impl NovaCity {
+    pub fn delivers_on(&self, day: Weekday) -> bool {
+        match day {
+            Weekday::Mon => self.delivery_monday,
+            Weekday::Tue => self.delivery_tuesday,
+            Weekday::Wed => self.delivery_wednesday,
+            Weekday::Thu => self.delivery_thursday,
+            Weekday::Fri => self.delivery_friday,
+            Weekday::Sat => self.delivery_saturday,
+            Weekday::Sun => self.delivery_sunday,
+        }
+    }
+}
+
+/// Settlement from the full `getSettlements` directory.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaSettlement {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    pub description: String,
+    #[serde(default)]
+    pub description_ru: String,
+    #[serde(rename = "SettlementType", default)]
+    pub settlement_type_id: String,
+    #[serde(default)]
+    pub settlement_type_description: String,
+    #[serde(rename = "Area", default)]
+    pub area_id: String,
+    #[serde(default)]
+    pub area_description: String,
+    #[serde(rename = "Region", default)]
+    pub region_id: String,
+    #[serde(default)]
+    pub regions_description: String,
+    #[serde(default, deserialize_with = "de::number")]
+    pub latitude: f64,
+    #[serde(default, deserialize_with = "de::number")]
+    pub longitude: f64,
+    /// Whether the settlement has at least one warehouse.
+    #[serde(rename = "Warehouse", default, deserialize_with = "de::flag")]
+    pub has_warehouse: bool,
+}
+
+/// One page of `searchSettlements`/`searchSettlementStreets` matches.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaSettlementSearch<T> {
+    #[serde(default, deserialize_with = "de::number")]
+    pub total_count: u32,
+    #[serde(default = "Vec::new")]
+    pub addresses: Vec<T>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaSettlementAddress {
+    /// Settlement ref, used for street search.
+    #[serde(rename = "Ref")]
+    pub id: String,
+    /// City ref, used for prices and delivery dates.
+    #[serde(rename = "DeliveryCity")]
+    pub delivery_city_id: String,
+    /// Full name for display, like "м. Київ, Київська обл."
+    pub present: String,
+    pub main_description: String,
+    #[serde(default)]
+    pub area: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub settlement_type_code: String,
+    #[serde(default)]
+    pub parent_region_types: String,
+    #[serde(default)]
+    pub parent_region_code: String,
+    #[serde(default)]
+    pub region_types: String,
+    #[serde(default)]
+    pub region_types_code: String,
+    #[serde(rename = "Warehouses", default, deserialize_with = "de::number")]
+    pub warehouses_count: u32,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub address_delivery_allowed: bool,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub streets_availability: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaSettlementStreet {
+    #[serde(rename = "SettlementStreetRef")]
+    pub id: String,
+    #[serde(rename = "SettlementRef")]
+    pub settlement_id: String,
+    /// Name with its type, like "вул. Хрещатик"
+    pub present: String,
+    #[serde(rename = "SettlementStreetDescription")]
+    pub description: String,
+    #[serde(rename = "SettlementStreetDescriptionRu", default)]
+    pub description_ru: String,
+    #[serde(rename = "StreetsType", default)]
+    pub streets_type_id: String,
+    #[serde(default)]
+    pub streets_type_description: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NovaStreet {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    pub description: String,
+    /// Short type, like "вул." or "просп."
+    #[serde(default)]
+    pub streets_type: String,
+    #[serde(rename = "StreetsTypeRef", default)]
+    pub streets_type_id: String,
+}
+
+/// Address saved for a counterparty, used as `SenderAddress`/`RecipientAddress`.
+///
+/// `Address.save` only fills in the ref and description.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct NovaCounterpartyAddress {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    pub description: String,
+    pub city_ref: String,
+    pub city_description: String,
+    pub street_ref: String,
+    pub street_description: String,
+    pub building_description: String,
+    pub flat: String,
+    pub note: String,
+    pub address_name: String,
+}
@@ -3,46 +3,191 @@ use core::fmt;
 use chrono::{DateTime, Datelike, TimeZone};
 use serde::{Deserialize, Serialize};
 
+use crate::error::ValidationError;
+
+use super::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NovaCargoType {
+    #[default]
+    Parcel,
+    Cargo,
+    Documents,
+    TiresWheels,
+    Pallet,
+    /// Only used for cash on delivery sent back to the sender.
+    Money,
+}
+
+/// Tyres or wheels of one kind, the ref comes from `Common.getTiresWheelsList`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CargoDetails {
+    #[serde(rename = "CargoDescription")]
+    pub tires_wheels_ref: String,
+    pub amount: u32,
+}
+
+impl CargoDetails {
+    pub fn new(tires_wheels_ref: &str, amount: u32) -> Self {
+        CargoDetails {
+            tires_wheels_ref: tires_wheels_ref.to_owned(),
+            amount,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Cargo {
-    pub cost: i32,
+    pub cost: Money,
     pub options_seat: NovaOptionsSeat,
     pub payment_on_delivery: bool,
     pub description: String,
+    pub cargo_type: NovaCargoType,
+    /// Filled in for `TiresWheels` only.
+    pub details: Vec<CargoDetails>,
 }
 
 impl Cargo {
-    pub fn new(cost: i32, options: NovaOptionsSeat, payment: bool, description: String) -> Self {
+    pub fn new(
+        cost: impl Into<Money>,
+        options: NovaOptionsSeat,
+        payment: bool,
+        description: String,
+    ) -> Self {
         Cargo {
-            cost,
+            cost: cost.into(),
             options_seat: options,
             payment_on_delivery: payment,
             description,
+            cargo_type: NovaCargoType::Parcel,
+            details: vec![],
         }
     }
+
+    pub fn cargo_type(mut self, cargo_type: NovaCargoType) -> Self {
+        self.cargo_type = cargo_type;
+        self
+    }
+
+    /// Tyres or wheels, switches the cargo type to `TiresWheels`.
+    pub fn tires_wheels(mut self, details: CargoDetails) -> Self {
+        self.cargo_type = NovaCargoType::TiresWheels;
+        self.details.push(details);
+        self
+    }
+
+    /// Palletised goods, switches the cargo type to `Pallet`.
+    pub fn pallet(mut self, pallet_ref: &str, pack_ref: Option<&str>) -> Self {
+        self.cargo_type = NovaCargoType::Pallet;
+        self.options_seat.pallet_ref = Some(pallet_ref.to_owned());
+        self.options_seat.pack_ref = pack_ref.map(str::to_owned);
+        self
+    }
 }
+
+#[allow(clippy::wrong_self_convention)]
 pub trait CargoSplit {
-    fn into_ttn_values(&self) -> (f32, i32, i32, String);
+    fn into_ttn_values(&self) -> (f32, Money, Money, String);
+    /// One `OptionsSeat` entry per cargo, in order.
+    fn options_seat(&self) -> Vec<NovaOptionsSeat>;
+    /// The document has a single cargo type, the one shared by every seat.
+    fn cargo_type(&self) -> NovaCargoType;
+    /// Tyre and wheel details of all seats.
+    fn cargo_details(&self) -> Vec<CargoDetails>;
+    fn validate(&self) -> Result<(), ValidationError>;
 }
 
 impl CargoSplit for Vec<Cargo> {
-    fn into_ttn_values(&self) -> (f32, i32, i32, String) {
+    /// Total weight, cost, cash on delivery and the distinct descriptions joined.
+    ///
+    /// Sums saturate, `validate` reports totals that do not fit.
+    fn into_ttn_values(&self) -> (f32, Money, Money, String) {
         let mut total = 0f32;
-        let mut price = 0;
-        let mut to_payment_ammount = 0;
-        let mut description: String = "".to_string();
-        for c in self.into_iter() {
+        let mut price = Money::ZERO;
+        let mut to_payment_ammount = Money::ZERO;
+        let mut descriptions: Vec<&str> = vec![];
+        for c in self.iter() {
             total += c.options_seat.weight;
-            price += c.cost;
-            description = c.description.clone();
+            price = price.saturating_add(c.cost);
+            let description = c.description.trim();
+            if !description.is_empty() && !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
             if c.payment_on_delivery {
-                to_payment_ammount += c.cost;
+                to_payment_ammount = to_payment_ammount.saturating_add(c.cost);
             }
         }
-        (total, price, to_payment_ammount, description)
+        (total, price, to_payment_ammount, descriptions.join(", "))
     }
+
+    fn options_seat(&self) -> Vec<NovaOptionsSeat> {
+        self.iter().map(|c| c.options_seat.clone()).collect()
+    }
+
+    fn cargo_type(&self) -> NovaCargoType {
+        self.first().map(|c| c.cargo_type).unwrap_or_default()
+    }
+
+    fn cargo_details(&self) -> Vec<CargoDetails> {
+        self.iter()
+            .flat_map(|c| c.details.iter().cloned())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        if self.is_empty() {
+            errors.push("cargos", "You cannot send 0 cargos");
+        }
+        let cargo_type = self.cargo_type();
+        for (i, cargo) in self.iter().enumerate() {
+            if cargo.cargo_type != cargo_type {
+                errors.push(
+                    format!("cargos[{}].cargo_type", i),
+                    format!(
+                        "{:?} can not be sent together with {:?}",
+                        cargo.cargo_type, cargo_type
+                    ),
+                );
+            }
+            match cargo.cargo_type {
+                NovaCargoType::Money => {
+                    errors.push(
+                        format!("cargos[{}].cargo_type", i),
+                        "money is only sent back",
+                    );
+                }
+                NovaCargoType::TiresWheels if cargo.details.is_empty() => {
+                    errors.push(
+                        format!("cargos[{}].details", i),
+                        "tyres and wheels are required",
+                    );
+                }
+                NovaCargoType::Pallet if cargo.options_seat.pallet_ref.is_none() => {
+                    errors.push(
+                        format!("cargos[{}].options_seat.pallet_ref", i),
+                        "is required",
+                    );
+                }
+                _ => {}
+            }
+        }
+        if checked_total(self.iter()).is_none() {
+            errors.push("cargos", "total cost is too large");
+        }
+        if checked_total(self.iter().filter(|c| c.payment_on_delivery)).is_none() {
+            errors.push("cargos", "total cash on delivery is too large");
+        }
+        errors.into_result()
+    }
+}
+
+fn checked_total<'a>(mut cargos: impl Iterator<Item = &'a Cargo>) -> Option<Money> {
+    cargos.try_fold(Money::ZERO, |sum, c| sum.checked_add(c.cost))
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait NovaTime {
     fn into_ttn_time(&self) -> String;
 }
@@ -53,18 +198,62 @@ impl<Tz: TimeZone> NovaTime for DateTime<Tz> {
     }
 }
 
+/// One seat of a shipment, dimensions in centimeters and weight in kilograms.
+///
+/// Sent as `OptionsSeat` with `volumetricVolume` derived from the dimensions, `Volume`
+/// is only sent for seats without them.
 #[allow(non_snake_case)]
 #[derive(Serialize, Debug, Clone)]
-#[serde(default)]
+#[serde(into = "OptionsSeatPayload")]
 pub struct NovaOptionsSeat {
+    /// Cubic meters.
     pub Volume: f32,
     pub Width: i32,
     pub Length: i32,
     pub Height: i32,
     pub weight: f32,
+    /// Pallet type, for `Pallet` cargo.
+    pub pallet_ref: Option<String>,
+    /// Packaging ordered from Nova Poshta, from `Common.getPackList`.
+    pub pack_ref: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OptionsSeatPayload {
+    volumetric_volume: f32,
+    volumetric_width: i32,
+    volumetric_length: i32,
+    volumetric_height: i32,
+    weight: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pallet_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pack_ref: Option<String>,
+}
+
+impl From<NovaOptionsSeat> for OptionsSeatPayload {
+    fn from(seat: NovaOptionsSeat) -> Self {
+        let measured = seat.Width > 0 && seat.Length > 0 && seat.Height > 0;
+        OptionsSeatPayload {
+            volumetric_volume: if measured {
+                seat.volume_m3() as f32
+            } else {
+                seat.Volume
+            },
+            volumetric_width: seat.Width,
+            volumetric_length: seat.Length,
+            volumetric_height: seat.Height,
+            weight: seat.weight,
+            pallet_ref: seat.pallet_ref,
+            pack_ref: seat.pack_ref,
+        }
+    }
 }
 
 impl NovaOptionsSeat {
+    /// `volume` is only used when the dimensions are unknown, prefer
+    /// [`NovaOptionsSeat::from_dimensions`].
     pub fn new(volume: f32, width: i32, lenght: i32, height: i32, weight: f32) -> Self {
         NovaOptionsSeat {
             Volume: volume,
@@ -72,40 +261,28 @@ impl NovaOptionsSeat {
             Length: lenght,
             Height: height,
             weight,
+            pallet_ref: None,
+            pack_ref: None,
         }
     }
 }
 
-pub enum NovaParcelWightStandarts {
-    UpToHalfKilogram,
-}
-
-impl From<NovaParcelWightStandarts> for NovaOptionsSeat {
-    fn from(s: NovaParcelWightStandarts) -> Self {
-        match s {
-            NovaParcelWightStandarts::UpToHalfKilogram => NovaOptionsSeat {
-                Volume: 0.5,
-                Width: 20,
-                Length: 20,
-                Height: 5,
-                weight: 0.5,
-            },
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NovaServiceType {
     WarehouseWarehouse,
     WarehouseDoors,
     DoorsWarehouse,
     DoorsDoors,
+    WarehousePostomat,
+    DoorsPostomat,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum NovaPaymentMethod {
+    #[default]
     Cash,
-    NoCash,
+    /// Bank transfer, only for organisations with a contract.
+    NonCash,
 }
 
 impl fmt::Display for NovaPaymentMethod {
@@ -119,3 +296,120 @@ impl fmt::Display for NovaServiceType {
         write!(f, "{:?}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cargo(cost: Money, payment_on_delivery: bool) -> Cargo {
+        Cargo::new(
+            cost,
+            NovaOptionsSeat::from_dimensions(20, 30, 10, 1.0),
+            payment_on_delivery,
+            "box".to_string(),
+        )
+    }
+
+    #[test]
+    fn volume_follows_the_dimensions() {
+        let seat = NovaOptionsSeat::new(5.0, 40, 30, 20, 2.0);
+        let json = serde_json::to_value(&seat).unwrap();
+        assert!((json["volumetricVolume"].as_f64().unwrap() - 0.024).abs() < 1e-6);
+        assert_eq!(json["volumetricWidth"], 40);
+        assert_eq!(json["volumetricLength"], 30);
+        assert_eq!(json["volumetricHeight"], 20);
+        assert_eq!(json["weight"], 2.0);
+
+        let unmeasured = NovaOptionsSeat::new(0.5, 0, 0, 0, 1.0);
+        let json = serde_json::to_value(&unmeasured).unwrap();
+        assert_eq!(json["volumetricVolume"], 0.5);
+    }
+
+    fn fields(cargos: &Vec<Cargo>) -> Vec<String> {
+        match cargos.validate() {
+            Ok(()) => vec![],
+            Err(e) => e.fields.into_iter().map(|f| f.field).collect(),
+        }
+    }
+
+    #[test]
+    fn cargo_types_follow_their_rules() {
+        let parcel = || cargo(Money::from_uah(100), false);
+        assert!(fields(&vec![parcel(), parcel()]).is_empty());
+        assert_eq!(fields(&vec![]), ["cargos"]);
+        assert_eq!(
+            fields(&vec![
+                parcel(),
+                parcel().cargo_type(NovaCargoType::Documents)
+            ]),
+            ["cargos[1].cargo_type"]
+        );
+        assert_eq!(
+            fields(&vec![parcel().cargo_type(NovaCargoType::Money)]),
+            ["cargos[0].cargo_type"]
+        );
+        assert_eq!(
+            fields(&vec![parcel().cargo_type(NovaCargoType::TiresWheels)]),
+            ["cargos[0].details"]
+        );
+        assert_eq!(
+            fields(&vec![parcel().cargo_type(NovaCargoType::Pallet)]),
+            ["cargos[0].options_seat.pallet_ref"]
+        );
+
+        let tyres = vec![
+            parcel().tires_wheels(CargoDetails::new("r16", 4)),
+            parcel().tires_wheels(CargoDetails::new("r17", 2)),
+        ];
+        assert!(fields(&tyres).is_empty());
+        assert_eq!(tyres.cargo_type(), NovaCargoType::TiresWheels);
+        assert_eq!(
+            serde_json::to_value(tyres.cargo_details()).unwrap(),
+            serde_json::json!([
+                { "CargoDescription": "r16", "Amount": 4 },
+                { "CargoDescription": "r17", "Amount": 2 }
+            ])
+        );
+        assert!(vec![parcel()].cargo_details().is_empty());
+    }
+
+    #[test]
+    fn pallet_refs_go_into_options_seat() {
+        let pallets = vec![
+            cargo(Money::from_uah(100), false).pallet("euro", Some("film")),
+            cargo(Money::from_uah(100), false).pallet("half", None),
+        ];
+        assert!(fields(&pallets).is_empty());
+        assert_eq!(pallets.cargo_type(), NovaCargoType::Pallet);
+        let seats = serde_json::to_value(pallets.options_seat()).unwrap();
+        assert_eq!(seats[0]["palletRef"], "euro");
+        assert_eq!(seats[0]["packRef"], "film");
+        assert_eq!(seats[1]["palletRef"], "half");
+        assert!(seats[1].get("packRef").is_none());
+
+        let parcel = serde_json::to_value(cargo(Money::ZERO, false).options_seat).unwrap();
+        assert!(parcel.get("palletRef").is_none());
+    }
+
+    #[test]
+    fn overflowing_totals_are_rejected() {
+        let huge = Money::from_kopecks(i64::MAX);
+        let cargos = vec![cargo(huge, true), cargo(Money::from_uah(1), true)];
+        let messages: Vec<String> = cargos
+            .validate()
+            .unwrap_err()
+            .fields
+            .into_iter()
+            .map(|f| f.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "total cost is too large",
+                "total cash on delivery is too large"
+            ]
+        );
+        let (_, price, to_payment, _) = cargos.into_ttn_values();
+        assert_eq!((price, to_payment), (huge, huge));
+    }
+}
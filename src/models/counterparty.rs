@@ -0,0 +1,378 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::de;
+use crate::error::ValidationError;
+
+/// `getCounterparties` always answers with pages of this size.
+pub const COUNTERPARTIES_PAGE_LIMIT: u32 = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NovaCounterpartyType {
+    PrivatePerson,
+    Organization,
+}
+
+/// Role the counterparty is stored under in the cabinet.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NovaCounterpartyProperty {
+    Sender,
+    Recipient,
+    ThirdPerson,
+}
+
+/// Payload of `Counterparty.save`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct NewCounterparty {
+    pub counterparty_type: NovaCounterpartyType,
+    pub counterparty_property: NovaCounterpartyProperty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(rename = "EDRPOU", skip_serializing_if = "Option::is_none")]
+    pub edrpou: Option<String>,
+    /// Ref from `Common.getOwnershipFormsList`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ownership_form: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city_ref: Option<String>,
+}
+
+impl NewCounterparty {
+    /// Private person stored as a recipient.
+    pub fn private_person(first_name: &str, last_name: &str, phone: &str) -> Self {
+        NewCounterparty {
+            counterparty_type: NovaCounterpartyType::PrivatePerson,
+            counterparty_property: NovaCounterpartyProperty::Recipient,
+            first_name: Some(first_name.to_owned()),
+            middle_name: None,
+            last_name: Some(last_name.to_owned()),
+            phone: Some(phone.to_owned()),
+            email: None,
+            edrpou: None,
+            ownership_form: None,
+            city_ref: None,
+        }
+    }
+
+    /// Organisation found by its EDRPOU code, stored as a recipient.
+    pub fn organization(edrpou: &str) -> Self {
+        NewCounterparty {
+            counterparty_type: NovaCounterpartyType::Organization,
+            counterparty_property: NovaCounterpartyProperty::Recipient,
+            first_name: None,
+            middle_name: None,
+            last_name: None,
+            phone: None,
+            email: None,
+            edrpou: Some(edrpou.to_owned()),
+            ownership_form: None,
+            city_ref: None,
+        }
+    }
+
+    pub fn property(mut self, property: NovaCounterpartyProperty) -> Self {
+        self.counterparty_property = property;
+        self
+    }
+
+    pub fn middle_name(mut self, middle_name: &str) -> Self {
+        self.middle_name = Some(middle_name.to_owned());
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_owned());
+        self
+    }
+
+    pub fn ownership_form(mut self, ownership_form_ref: &str) -> Self {
+        self.ownership_form = Some(ownership_form_ref.to_owned());
+        self
+    }
+
+    pub fn city_ref(mut self, city_ref: &str) -> Self {
+        self.city_ref = Some(city_ref.to_owned());
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        match self.counterparty_type {
+            NovaCounterpartyType::PrivatePerson => {
+                if is_blank(&self.first_name) {
+                    errors.push("counterparty.first_name", "is required");
+                }
+                if is_blank(&self.last_name) {
+                    errors.push("counterparty.last_name", "is required");
+                }
+                if !is_phone(self.phone.as_deref().unwrap_or_default()) {
+                    errors.push(
+                        "counterparty.phone",
+                        "must be a phone number like 380991234567",
+                    );
+                }
+            }
+            NovaCounterpartyType::Organization => {
+                if !is_edrpou(self.edrpou.as_deref().unwrap_or_default()) {
+                    errors.push("counterparty.edrpou", "must be 8 or 10 digits");
+                }
+            }
+        }
+        errors.into_result()
+    }
+}
+
+/// Payload of `ContactPerson.save` and `ContactPerson.update`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContactPerson {
+    pub first_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    pub last_name: String,
+    pub phone: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl ContactPerson {
+    pub fn new(first_name: &str, last_name: &str, phone: &str) -> Self {
+        ContactPerson {
+            first_name: first_name.to_owned(),
+            middle_name: None,
+            last_name: last_name.to_owned(),
+            phone: phone.to_owned(),
+            email: None,
+        }
+    }
+
+    pub fn middle_name(mut self, middle_name: &str) -> Self {
+        self.middle_name = Some(middle_name.to_owned());
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_owned());
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        if self.first_name.trim().is_empty() {
+            errors.push("contact_person.first_name", "is required");
+        }
+        if self.last_name.trim().is_empty() {
+            errors.push("contact_person.last_name", "is required");
+        }
+        if !is_phone(&self.phone) {
+            errors.push(
+                "contact_person.phone",
+                "must be a phone number like 380991234567",
+            );
+        }
+        errors.into_result()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct NovaContactPerson {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    pub description: String,
+    pub first_name: String,
+    pub middle_name: String,
+    pub last_name: String,
+    pub phones: String,
+    pub email: String,
+}
+
+/// Counterparty as returned by `Counterparty.save` and `getCounterparties`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct NovaCounterparty {
+    #[serde(rename = "Ref")]
+    pub id: String,
+    #[serde(rename = "Description")]
+    pub full_name: String,
+    pub first_name: String,
+    pub middle_name: String,
+    pub last_name: String,
+    #[serde(rename = "City")]
+    pub city_ref: String,
+    #[serde(rename = "EDRPOU")]
+    pub edrpou: String,
+    #[serde(rename = "OwnershipFormRef", alias = "OwnershipForm")]
+    pub ownership_form: String,
+    pub ownership_form_description: String,
+    pub counterparty_type: Option<NovaCounterpartyType>,
+    /// Contact persons created together with the counterparty, only sent by `save`.
+    #[serde(rename = "ContactPerson", deserialize_with = "contact_persons")]
+    pub contact_persons: Vec<NovaContactPerson>,
+}
+
+/// What the cabinet allows for a counterparty, from `getCounterpartyOptions`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct NovaCounterpartyOptions {
+    #[serde(deserialize_with = "de::flag")]
+    pub can_pay_the_third_person: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_non_cash_payment: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_afterpayment_on_goods_cost: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_credit_documents: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_signed_documents: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_sent_from_postomat: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_same_day_delivery: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub can_forwarding_service: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub address_document_delivery: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub hide_delivery_cost: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub have_money_wallets: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub customer_return: bool,
+}
+
+/// `{"success": true, "data": [...]}` nested in the counterparty.
+fn contact_persons<'de, D>(deserializer: D) -> Result<Vec<NovaContactPerson>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize, Default)]
+    struct Nested {
+        #[serde(default)]
+        data: Vec<NovaContactPerson>,
+    }
+    let nested: Nested = de::object_or_default(deserializer)?;
+    Ok(nested.data)
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
+
+pub(crate) fn is_phone(phone: &str) -> bool {
+    let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+    (10..=12).contains(&digits)
+}
+
+pub(crate) fn is_edrpou(edrpou: &str) -> bool {
+    let edrpou = edrpou.trim();
+    matches!(edrpou.len(), 8 | 10) && edrpou.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: Result<(), ValidationError>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(e) => e.fields.into_iter().map(|f| f.field).collect(),
+        }
+    }
+
+    #[test]
+    fn new_counterparties_are_validated() {
+        let person = NewCounterparty::private_person("Тарас", "Шевченко", "+38 (067) 123-45-67");
+        assert!(fields(person.validate()).is_empty());
+        assert_eq!(
+            fields(NewCounterparty::private_person(" ", "", "067").validate()),
+            [
+                "counterparty.first_name",
+                "counterparty.last_name",
+                "counterparty.phone"
+            ]
+        );
+
+        assert!(fields(NewCounterparty::organization("12345678").validate()).is_empty());
+        assert!(fields(NewCounterparty::organization("1234567890").validate()).is_empty());
+        for edrpou in ["1234567", "123456789", "1234567a"] {
+            assert_eq!(
+                fields(NewCounterparty::organization(edrpou).validate()),
+                ["counterparty.edrpou"],
+                "{}",
+                edrpou
+            );
+        }
+    }
+
+    #[test]
+    fn new_counterparty_payload_skips_missing_fields() {
+        let company = NewCounterparty::organization("12345678")
+            .property(NovaCounterpartyProperty::ThirdPerson)
+            .ownership_form("tov")
+            .city_ref("kyiv");
+        assert_eq!(
+            serde_json::to_value(company).unwrap(),
+            serde_json::json!({
+                "CounterpartyType": "Organization",
+                "CounterpartyProperty": "ThirdPerson",
+                "EDRPOU": "12345678",
+                "OwnershipForm": "tov",
+                "CityRef": "kyiv"
+            })
+        );
+    }
+
+    #[test]
+    fn contact_persons_are_validated() {
+        let person = ContactPerson::new("Тарас", "Шевченко", "380671234567");
+        assert!(fields(person.validate()).is_empty());
+        assert_eq!(
+            fields(ContactPerson::new("", " ", "phone").validate()),
+            [
+                "contact_person.first_name",
+                "contact_person.last_name",
+                "contact_person.phone"
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_nested_contact_persons() {
+        let saved: NovaCounterparty = serde_json::from_str(
+            r#"{
+                "Ref": "counterparty",
+                "Description": "Шевченко Тарас",
+                "FirstName": "Тарас",
+                "LastName": "Шевченко",
+                "CounterpartyType": "PrivatePerson",
+                "ContactPerson": {
+                    "success": true,
+                    "data": [{"Ref": "contact", "Description": "Шевченко Тарас", "Phones": "380671234567"}]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(saved.full_name, "Шевченко Тарас");
+        assert_eq!(
+            saved.counterparty_type,
+            Some(NovaCounterpartyType::PrivatePerson)
+        );
+        assert_eq!(saved.contact_persons.len(), 1);
+        assert_eq!(saved.contact_persons[0].id, "contact");
+        assert_eq!(saved.contact_persons[0].phones, "380671234567");
+
+        for missing in [r#"{"Ref": "c"}"#, r#"{"Ref": "c", "ContactPerson": []}"#] {
+            let found: NovaCounterparty = serde_json::from_str(missing).unwrap();
+            assert!(found.contact_persons.is_empty());
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use super::money::Money;
+
 #[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "PascalCase")]
 pub struct NovaShipmentCreated {
     pub int_doc_number: String,
     #[serde(rename = "Ref")]
     pub id: String,
     pub estimated_delivery_date: String,
+    #[serde(default)]
+    pub cost_on_site: Money,
 }
 
 impl NovaShipmentCreated {
@@ -24,12 +28,12 @@ pub struct NovaShipmentDelete {
     pub id: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
 pub struct NovaDocumentPrice {
-    pub cost: i32,
-    pub cost_redelivery: i32,
-    pub assessed_cost: i32,
+    pub cost: Money,
+    pub cost_redelivery: Money,
+    pub assessed_cost: Money,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -1,9 +1,21 @@
+pub mod address;
 pub mod cargo;
+pub mod counterparty;
 pub mod document;
+pub mod money;
+pub mod preset;
 pub mod recipient;
 pub mod sender;
+pub mod warehouse;
+pub mod weight;
 
+pub use address::*;
 pub use cargo::*;
+pub use counterparty::*;
 pub use document::*;
+pub use money::*;
+pub use preset::*;
 pub use recipient::*;
 pub use sender::*;
+pub use warehouse::*;
+pub use weight::*;
@@ -0,0 +1,232 @@
+use core::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Amount in hryvnias, stored as whole kopecks.
+///
+/// Sent to the api as a decimal string like `"1250.50"`, read back from strings or
+/// numbers. Arithmetic panics on overflow instead of wrapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_kopecks(kopecks: i64) -> Self {
+        Money(kopecks)
+    }
+
+    pub fn from_uah(uah: i64) -> Self {
+        Money(uah.checked_mul(100).expect("money amount overflow"))
+    }
+
+    pub fn kopecks(&self) -> i64 {
+        self.0
+    }
+
+    /// Whole hryvnias, kopecks are dropped.
+    pub fn uah(&self) -> i64 {
+        self.0 / 100
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    pub fn saturating_add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+
+    /// Rounds to the nearest kopeck, `None` for infinities, NaN and out of range amounts.
+    pub fn from_uah_f64(uah: f64) -> Option<Money> {
+        let kopecks = (uah * 100.0).round();
+        // i64::MAX as f64 rounds up to 2^63, which no longer fits
+        (kopecks >= i64::MIN as f64 && kopecks < i64::MAX as f64).then_some(Money(kopecks as i64))
+    }
+}
+
+/// Whole hryvnias, the unit `Cargo::cost` used to be in.
+impl From<i32> for Money {
+    fn from(uah: i32) -> Self {
+        Money::from_uah(uah as i64)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).expect("money amount overflow")
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).expect("money amount overflow")
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let kopecks = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, kopecks / 100, kopecks % 100)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoneyError(String);
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid money amount {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+/// Accepts `"1250"`, `"1250.5"`, `"1250,50"` and `"-3.00"`, at most two decimals.
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMoneyError(s.to_owned());
+        let text = s.trim().replace(',', ".");
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.as_str()),
+        };
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || fraction.len() > 2 || !digits(whole) || !digits(fraction) {
+            return Err(error());
+        }
+        let whole: i64 = whole.parse().map_err(|_| error())?;
+        let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| error())?;
+        let kopecks = whole
+            .checked_mul(100)
+            .and_then(|k| k.checked_add(fraction))
+            .ok_or_else(error)?;
+        Ok(Money(if negative { -kopecks } else { kopecks }))
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Empty strings and nulls are zero, floats are rounded to the nearest kopeck.
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(Money::ZERO),
+            Value::String(s) if s.trim().is_empty() => Ok(Money::ZERO),
+            Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+            Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(uah), _) => uah
+                    .checked_mul(100)
+                    .map(Money)
+                    .ok_or_else(|| serde::de::Error::custom("money amount overflow")),
+                (None, Some(uah)) => Money::from_uah_f64(uah)
+                    .ok_or_else(|| serde::de::Error::custom("money amount overflow")),
+                _ => Err(serde::de::Error::custom(format!(
+                    "invalid money amount {}",
+                    n
+                ))),
+            },
+            other => Err(serde::de::Error::custom(format!(
+                "invalid money amount {}",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_api_formats() {
+        assert_eq!("1250".parse(), Ok(Money::from_kopecks(125_000)));
+        assert_eq!("1250.5".parse(), Ok(Money::from_kopecks(125_050)));
+        assert_eq!(" 1250,05 ".parse(), Ok(Money::from_kopecks(125_005)));
+        assert_eq!("-3.00".parse(), Ok(Money::from_kopecks(-300)));
+        assert!("12.345".parse::<Money>().is_err());
+        assert!("abc".parse::<Money>().is_err());
+        assert!(".5".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn reads_strings_and_numbers() {
+        let amounts: Vec<Money> =
+            serde_json::from_str(r#"["45.10", 45, 45.1, "", null, 99999999999]"#).unwrap();
+        assert_eq!(
+            amounts,
+            vec![
+                Money::from_kopecks(4510),
+                Money::from_uah(45),
+                Money::from_kopecks(4510),
+                Money::ZERO,
+                Money::ZERO,
+                Money::from_uah(99_999_999_999),
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_decimal_strings() {
+        let json = serde_json::to_string(&[Money::from_kopecks(5), Money::from_kopecks(-125_050)])
+            .unwrap();
+        assert_eq!(json, r#"["0.05","-1250.50"]"#);
+    }
+
+    #[test]
+    fn sums_without_wrapping() {
+        let total: Money = [Money::from_uah(1), Money::from_kopecks(99)]
+            .into_iter()
+            .sum();
+        assert_eq!(total, Money::from_kopecks(199));
+        assert_eq!(Money::from_kopecks(i64::MAX).checked_add(total), None);
+    }
+
+    #[test]
+    fn rejects_out_of_range_floats() {
+        assert!(serde_json::from_str::<Money>("1e300").is_err());
+        assert!(serde_json::from_str::<Money>("-1e17").is_err());
+        assert_eq!(
+            serde_json::from_str::<Money>("-1e10").unwrap(),
+            Money::from_uah(-10_000_000_000)
+        );
+        assert_eq!(Money::from_uah_f64(f64::NAN), None);
+        assert_eq!(Money::from_uah_f64(0.015), Some(Money::from_kopecks(2)));
+    }
+}
@@ -0,0 +1,230 @@
+use super::cargo::NovaOptionsSeat;
+use super::warehouse::NovaDimensionsLimit;
+
+/// Standard Nova Poshta packaging, named after the weight it is meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NovaParcelWightStandarts {
+    EnvelopeA5,
+    EnvelopeA4,
+    UpToHalfKilogram,
+    UpToOneKilogram,
+    UpToTwoKilograms,
+    UpToThreeKilograms,
+    UpToFiveKilograms,
+    UpToTenKilograms,
+    UpToFifteenKilograms,
+    UpToTwentyKilograms,
+    UpToThirtyKilograms,
+    /// Half of a euro pallet, 80x60.
+    HalfPallet,
+    /// Euro pallet, 120x80.
+    EuroPallet,
+    /// Standard pallet, 120x100.
+    Pallet,
+    /// Tyre or wheel up to R15.
+    TireUpToR15,
+    /// Tyre or wheel R16-R17.
+    TireR16R17,
+    /// Tyre or wheel R18-R19.
+    TireR18R19,
+    /// Tyre or wheel R20 and above.
+    TireR20Plus,
+}
+
+impl NovaParcelWightStandarts {
+    pub const ALL: [NovaParcelWightStandarts; 18] = [
+        NovaParcelWightStandarts::EnvelopeA5,
+        NovaParcelWightStandarts::EnvelopeA4,
+        NovaParcelWightStandarts::UpToHalfKilogram,
+        NovaParcelWightStandarts::UpToOneKilogram,
+        NovaParcelWightStandarts::UpToTwoKilograms,
+        NovaParcelWightStandarts::UpToThreeKilograms,
+        NovaParcelWightStandarts::UpToFiveKilograms,
+        NovaParcelWightStandarts::UpToTenKilograms,
+        NovaParcelWightStandarts::UpToFifteenKilograms,
+        NovaParcelWightStandarts::UpToTwentyKilograms,
+        NovaParcelWightStandarts::UpToThirtyKilograms,
+        NovaParcelWightStandarts::HalfPallet,
+        NovaParcelWightStandarts::EuroPallet,
+        NovaParcelWightStandarts::Pallet,
+        NovaParcelWightStandarts::TireUpToR15,
+        NovaParcelWightStandarts::TireR16R17,
+        NovaParcelWightStandarts::TireR18R19,
+        NovaParcelWightStandarts::TireR20Plus,
+    ];
+
+    /// Width, length and height in centimeters.
+    pub fn dimensions(&self) -> (i32, i32, i32) {
+        use NovaParcelWightStandarts::*;
+        match self {
+            EnvelopeA5 => (16, 23, 1),
+            EnvelopeA4 => (24, 32, 2),
+            UpToHalfKilogram => (12, 17, 9),
+            UpToOneKilogram => (17, 24, 10),
+            UpToTwoKilograms => (24, 34, 10),
+            UpToThreeKilograms => (26, 36, 12),
+            UpToFiveKilograms => (24, 40, 21),
+            UpToTenKilograms => (35, 40, 28),
+            UpToFifteenKilograms => (35, 60, 28),
+            UpToTwentyKilograms => (40, 60, 33),
+            UpToThirtyKilograms => (40, 60, 50),
+            HalfPallet => (60, 80, 170),
+            EuroPallet => (80, 120, 170),
+            Pallet => (100, 120, 170),
+            TireUpToR15 => (65, 65, 20),
+            TireR16R17 => (70, 70, 23),
+            TireR18R19 => (75, 75, 25),
+            TireR20Plus => (80, 80, 28),
+        }
+    }
+
+    /// Heaviest load the packaging is meant for, in kilograms.
+    pub fn max_weight(&self) -> f32 {
+        use NovaParcelWightStandarts::*;
+        match self {
+            EnvelopeA5 => 0.5,
+            EnvelopeA4 => 1.0,
+            UpToHalfKilogram => 0.5,
+            UpToOneKilogram => 1.0,
+            UpToTwoKilograms => 2.0,
+            UpToThreeKilograms => 3.0,
+            UpToFiveKilograms => 5.0,
+            UpToTenKilograms => 10.0,
+            UpToFifteenKilograms => 15.0,
+            UpToTwentyKilograms => 20.0,
+            UpToThirtyKilograms => 30.0,
+            HalfPallet => 500.0,
+            EuroPallet => 1000.0,
+            Pallet => 1000.0,
+            TireUpToR15 => 10.0,
+            TireR16R17 => 15.0,
+            TireR18R19 => 20.0,
+            TireR20Plus => 30.0,
+        }
+    }
+
+    pub fn is_envelope(&self) -> bool {
+        matches!(
+            self,
+            NovaParcelWightStandarts::EnvelopeA5 | NovaParcelWightStandarts::EnvelopeA4
+        )
+    }
+
+    pub fn is_pallet(&self) -> bool {
+        matches!(
+            self,
+            NovaParcelWightStandarts::HalfPallet
+                | NovaParcelWightStandarts::EuroPallet
+                | NovaParcelWightStandarts::Pallet
+        )
+    }
+
+    pub fn is_tire(&self) -> bool {
+        matches!(
+            self,
+            NovaParcelWightStandarts::TireUpToR15
+                | NovaParcelWightStandarts::TireR16R17
+                | NovaParcelWightStandarts::TireR18R19
+                | NovaParcelWightStandarts::TireR20Plus
+        )
+    }
+
+    /// Seat in this packaging with the weight of what is actually packed, in kilograms.
+    pub fn seat(&self, weight: f32) -> NovaOptionsSeat {
+        let (width, length, height) = self.dimensions();
+        NovaOptionsSeat::from_dimensions(width, length, height, weight)
+    }
+
+    /// Cubic centimeters.
+    fn capacity(&self) -> i64 {
+        let (width, length, height) = self.dimensions();
+        width as i64 * length as i64 * height as i64
+    }
+
+    /// Whether `items` can be packed together: each one fits in some orientation and
+    /// their total volume and weight stay within the packaging.
+    pub fn fits(&self, items: &[NovaOptionsSeat]) -> bool {
+        let (width, length, height) = self.dimensions();
+        let limit = NovaDimensionsLimit {
+            width: width as f64,
+            height: height as f64,
+            length: length as f64,
+        };
+        let each_fits = items
+            .iter()
+            .all(|i| limit.fits(i.Width as f64, i.Height as f64, i.Length as f64));
+        let volume: i64 = items
+            .iter()
+            .map(|i| i.Width as i64 * i.Length as i64 * i.Height as i64)
+            .sum();
+        let weight: f32 = items.iter().map(|i| i.weight).sum();
+        each_fits && volume <= self.capacity() && weight <= self.max_weight()
+    }
+
+    /// Smallest envelope, box or pallet the items fit in, tyre presets are never picked.
+    pub fn smallest_fitting(items: &[NovaOptionsSeat]) -> Option<NovaParcelWightStandarts> {
+        NovaParcelWightStandarts::ALL
+            .into_iter()
+            .filter(|p| !p.is_tire() && p.fits(items))
+            .min_by(|a, b| {
+                a.capacity()
+                    .cmp(&b.capacity())
+                    .then(a.max_weight().total_cmp(&b.max_weight()))
+            })
+    }
+}
+
+/// Seat with the preset dimensions, weighing the most the preset allows. Use
+/// [`NovaParcelWightStandarts::seat`] when the actual weight is known.
+impl From<NovaParcelWightStandarts> for NovaOptionsSeat {
+    fn from(s: NovaParcelWightStandarts) -> Self {
+        s.seat(s.max_weight())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_match_their_weight_class() {
+        for preset in NovaParcelWightStandarts::ALL
+            .into_iter()
+            .filter(|p| !p.is_envelope() && !p.is_pallet() && !p.is_tire())
+        {
+            let seat = NovaOptionsSeat::from(preset);
+            let ratio = seat.volumetric_weight() / preset.max_weight() as f64;
+            assert!((0.9..=1.1).contains(&ratio), "{:?}: {}", preset, ratio);
+        }
+    }
+
+    #[test]
+    fn seat_keeps_the_actual_weight() {
+        let seat = NovaParcelWightStandarts::UpToTenKilograms.seat(2.5);
+        assert_eq!((seat.Width, seat.Length, seat.Height), (35, 40, 28));
+        assert_eq!(seat.weight, 2.5);
+        assert_eq!(seat.billable_weight(), 9.8);
+    }
+
+    #[test]
+    fn picks_smallest_preset() {
+        let phone = NovaOptionsSeat::from_dimensions(8, 16, 5, 0.3);
+        assert_eq!(
+            NovaParcelWightStandarts::smallest_fitting(std::slice::from_ref(&phone)),
+            Some(NovaParcelWightStandarts::UpToHalfKilogram)
+        );
+        assert_eq!(
+            NovaParcelWightStandarts::smallest_fitting(&[phone.clone(), phone.clone(), phone]),
+            Some(NovaParcelWightStandarts::UpToOneKilogram)
+        );
+
+        let papers = NovaOptionsSeat::from_dimensions(21, 30, 1, 0.2);
+        assert_eq!(
+            NovaParcelWightStandarts::smallest_fitting(&[papers]),
+            Some(NovaParcelWightStandarts::EnvelopeA4)
+        );
+
+        let ladder = NovaOptionsSeat::from_dimensions(20, 250, 10, 8.0);
+        assert_eq!(NovaParcelWightStandarts::smallest_fitting(&[ladder]), None);
+    }
+}
@@ -1,55 +1,217 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::ValidationError;
+
+use super::address::{NovaSettlementAddress, NovaSettlementStreet};
+use super::cargo::NovaPaymentMethod;
 use super::cargo::NovaServiceType;
+use super::counterparty::{is_edrpou, is_phone, NovaCounterpartyType};
+
+/// Warehouse or postomat, either by its ref or by its number in the recipient city.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum WarehouseId {
+    Ref(String),
+    Number(String),
+}
+
+/// Street for door delivery, either its ref or its name for the api to look up.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum StreetId {
+    Ref(String),
+    Name(String),
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Address {
-    pub warehouse_number: Option<String>,
-    pub address_name: Option<String>,
-    pub address_house: Option<String>,
-    pub address_flat: Option<String>,
-    pub pochtomat_number: Option<String>,
-    pub service_type: NovaServiceType,
+/// Where the recipient picks the parcel up.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    Warehouse(WarehouseId),
+    Postomat(WarehouseId),
+    Door {
+        street: StreetId,
+        house: String,
+        flat: Option<String>,
+    },
+    /// Door delivery to an address saved for the recipient with `door_address` or
+    /// `create_address`.
+    SavedDoor(String),
 }
 
 impl Address {
     pub fn warehouse(warehouse_number: i32) -> Self {
-        Address {
-            warehouse_number: Some(warehouse_number.to_string()),
-            address_name: None,
-            address_house: None,
-            address_flat: None,
-            pochtomat_number: None,
-            service_type: NovaServiceType::WarehouseWarehouse,
-        }
+        Address::Warehouse(WarehouseId::Number(warehouse_number.to_string()))
+    }
+    pub fn warehouse_ref(warehouse_ref: &str) -> Self {
+        Address::Warehouse(WarehouseId::Ref(warehouse_ref.to_owned()))
     }
+    /// Door delivery by street name, the api guesses the street and may pick the
+    /// wrong one. Prefer [`Address::street`].
+    #[allow(clippy::self_named_constructors)]
     pub fn address(address_name: String, address_house: String, apartment_number: String) -> Self {
-        Address {
-            warehouse_number: None,
-            address_name: Some(address_name),
-            address_house: Some(address_house),
-            address_flat: Some(apartment_number),
-            pochtomat_number: None,
-            service_type: NovaServiceType::WarehouseDoors,
+        Address::door(
+            StreetId::Name(address_name),
+            address_house,
+            apartment_number,
+        )
+    }
+    /// Door delivery to a street resolved by `get_streets` or `search_settlement_streets`,
+    /// the api does not have to guess the street from its name.
+    pub fn street(street_ref: &str, address_house: String, apartment_number: String) -> Self {
+        Address::door(
+            StreetId::Ref(street_ref.to_owned()),
+            address_house,
+            apartment_number,
+        )
+    }
+    fn door(street: StreetId, house: String, apartment_number: String) -> Self {
+        Address::Door {
+            street,
+            house,
+            flat: Some(apartment_number).filter(|f| !f.trim().is_empty()),
         }
     }
+    /// Door delivery to a street found by `search_settlement_streets`.
+    pub fn settlement_street(
+        street: &NovaSettlementStreet,
+        address_house: String,
+        apartment_number: String,
+    ) -> Self {
+        Address::street(&street.id, address_house, apartment_number)
+    }
+    /// Door delivery to an address already saved for the recipient counterparty, nothing
+    /// is looked up or saved when the document is created.
+    pub fn saved_door(address_ref: &str) -> Self {
+        Address::SavedDoor(address_ref.to_owned())
+    }
     pub fn pochtomat(pochtomat_number: i32) -> Self {
-        Address {
-            warehouse_number: None,
-            address_name: None,
-            address_house: None,
-            address_flat: None,
-            pochtomat_number: Some(pochtomat_number.to_string()),
-            service_type: NovaServiceType::WarehouseWarehouse,
+        Address::Postomat(WarehouseId::Number(pochtomat_number.to_string()))
+    }
+    pub fn pochtomat_ref(pochtomat_ref: &str) -> Self {
+        Address::Postomat(WarehouseId::Ref(pochtomat_ref.to_owned()))
+    }
+
+    pub fn is_door(&self) -> bool {
+        matches!(self, Address::Door { .. } | Address::SavedDoor(_))
+    }
+
+    /// Door delivery to an address saved under the recipient, either given by its ref
+    /// or found or saved for a street ref.
+    pub fn is_saved_door(&self) -> bool {
+        matches!(
+            self,
+            Address::SavedDoor(_)
+                | Address::Door {
+                    street: StreetId::Ref(_),
+                    ..
+                }
+        )
+    }
+
+    /// Service type when the sender drops the parcel off at a warehouse.
+    pub fn service_type(&self) -> NovaServiceType {
+        self.service_type_from(false)
+    }
+
+    /// Service type when the parcel is picked up at the sender's door or not.
+    pub fn service_type_from(&self, sender_door: bool) -> NovaServiceType {
+        match (sender_door, self) {
+            (false, Address::Warehouse(_)) => NovaServiceType::WarehouseWarehouse,
+            (false, Address::Postomat(_)) => NovaServiceType::WarehousePostomat,
+            (false, Address::Door { .. } | Address::SavedDoor(_)) => {
+                NovaServiceType::WarehouseDoors
+            }
+            (true, Address::Warehouse(_)) => NovaServiceType::DoorsWarehouse,
+            (true, Address::Postomat(_)) => NovaServiceType::DoorsPostomat,
+            (true, Address::Door { .. } | Address::SavedDoor(_)) => NovaServiceType::DoorsDoors,
         }
     }
+
+    /// Checks that the destination is complete.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        match self {
+            Address::Warehouse(id) => validate_id(&mut errors, "address.warehouse", id),
+            Address::Postomat(id) => validate_id(&mut errors, "address.postomat", id),
+            Address::Door { street, house, .. } => {
+                match street {
+                    StreetId::Ref(r) if r.trim().is_empty() => {
+                        errors.push("address.street", "ref is empty")
+                    }
+                    StreetId::Name(n) if n.trim().is_empty() => {
+                        errors.push("address.street", "name is empty")
+                    }
+                    _ => {}
+                }
+                if house.trim().is_empty() {
+                    errors.push("address.house", "is required for door delivery");
+                }
+            }
+            Address::SavedDoor(address_ref) => {
+                if address_ref.trim().is_empty() {
+                    errors.push("address", "ref is empty");
+                }
+            }
+        }
+        errors.into_result()
+    }
+}
+
+fn validate_id(errors: &mut ValidationError, field: &str, id: &WarehouseId) {
+    match id {
+        WarehouseId::Ref(r) if r.trim().is_empty() => errors.push(field, "ref is empty"),
+        WarehouseId::Number(n) if n.trim().is_empty() => errors.push(field, "number is empty"),
+        _ => {}
+    }
+}
+
+/// Who pays for the delivery.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum NovaPayer {
+    Sender,
+    Recipient,
+    /// Counterparty ref of a third person stored in the cabinet.
+    ThirdPerson(String),
+}
+
+impl NovaPayer {
+    pub fn payer_type(&self) -> &'static str {
+        match self {
+            NovaPayer::Sender => "Sender",
+            NovaPayer::Recipient => "Recipient",
+            NovaPayer::ThirdPerson(_) => "ThirdPerson",
+        }
+    }
+}
+
+/// Legal entity receiving the parcel, `full_name` of the recipient is the company name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecipientOrganization {
+    pub edrpou: String,
+    /// Ref from `Common.getOwnershipFormsList`.
+    pub ownership_form: String,
+    /// Person picking the parcel up on behalf of the company.
+    pub contact_name: String,
+}
+
+/// Recipient already stored in our cabinet, needed to save a street address for them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecipientCounterparty {
+    pub city_id: String,
+    pub counterparty_id: String,
+    pub contact_id: String,
 }
 
 pub struct Recipient {
     pub city_name: String,
+    pub area: Option<String>,
+    pub region: Option<String>,
+    pub settlement_type: Option<String>,
     pub full_name: String,
     pub phone: String,
-    pub is_payer: bool,
+    pub payer: NovaPayer,
+    pub payment_method: NovaPaymentMethod,
+    pub organization: Option<RecipientOrganization>,
+    /// Required for door delivery to a saved address or a street ref.
+    pub counterparty: Option<RecipientCounterparty>,
     pub address: Address,
 }
 
@@ -63,10 +225,263 @@ impl Recipient {
     ) -> Self {
         Recipient {
             city_name: city.to_owned(),
+            area: None,
+            region: None,
+            settlement_type: None,
             full_name,
             phone: phone.to_owned(),
-            is_payer,
+            payer: if is_payer {
+                NovaPayer::Recipient
+            } else {
+                NovaPayer::Sender
+            },
+            payment_method: NovaPaymentMethod::Cash,
+            organization: None,
+            counterparty: None,
             address,
         }
     }
+
+    /// Turns the recipient into a legal entity named `full_name`.
+    pub fn organization(
+        mut self,
+        edrpou: &str,
+        ownership_form_ref: &str,
+        contact_name: &str,
+    ) -> Self {
+        self.organization = Some(RecipientOrganization {
+            edrpou: edrpou.to_owned(),
+            ownership_form: ownership_form_ref.to_owned(),
+            contact_name: contact_name.to_owned(),
+        });
+        self
+    }
+
+    /// Refs from `create_counterparty` and `create_contact_person` or a counterparty
+    /// search, door delivery to a street ref finds or saves the address under this
+    /// counterparty.
+    pub fn counterparty(
+        mut self,
+        city_ref: &str,
+        counterparty_ref: &str,
+        contact_ref: &str,
+    ) -> Self {
+        self.counterparty = Some(RecipientCounterparty {
+            city_id: city_ref.to_owned(),
+            counterparty_id: counterparty_ref.to_owned(),
+            contact_id: contact_ref.to_owned(),
+        });
+        self
+    }
+
+    pub fn payer(mut self, payer: NovaPayer) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    pub fn payment_method(mut self, method: NovaPaymentMethod) -> Self {
+        self.payment_method = method;
+        self
+    }
+
+    pub fn recipient_type(&self) -> NovaCounterpartyType {
+        match self.organization {
+            Some(_) => NovaCounterpartyType::Organization,
+            None => NovaCounterpartyType::PrivatePerson,
+        }
+    }
+
+    /// Collects every missing or contradictory field instead of stopping at the first.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        if self.city_name.trim().is_empty() {
+            errors.push("recipient.city_name", "is required");
+        }
+        match &self.organization {
+            None if self.full_name.split_whitespace().count() < 2 => {
+                errors.push("recipient.full_name", "last and first name are required");
+            }
+            None => {}
+            Some(organization) => {
+                if self.full_name.trim().is_empty() {
+                    errors.push("recipient.full_name", "company name is required");
+                }
+                if !is_edrpou(&organization.edrpou) {
+                    errors.push("recipient.organization.edrpou", "must be 8 or 10 digits");
+                }
+                if organization.ownership_form.trim().is_empty() {
+                    errors.push("recipient.organization.ownership_form", "is required");
+                }
+                if organization.contact_name.split_whitespace().count() < 2 {
+                    errors.push(
+                        "recipient.organization.contact_name",
+                        "last and first name are required",
+                    );
+                }
+            }
+        }
+        if let NovaPayer::ThirdPerson(id) = &self.payer {
+            if id.trim().is_empty() {
+                errors.push("recipient.payer", "third person ref is empty");
+            }
+        }
+        if self.payment_method == NovaPaymentMethod::NonCash
+            && self.payer == NovaPayer::Recipient
+            && self.organization.is_none()
+        {
+            errors.push(
+                "recipient.payment_method",
+                "private persons can only pay cash",
+            );
+        }
+        match &self.counterparty {
+            None if self.address.is_saved_door() => {
+                errors.push(
+                    "recipient.counterparty",
+                    "is required for door delivery to a saved address or street ref",
+                );
+            }
+            Some(counterparty) => {
+                for (field, id) in [
+                    ("recipient.counterparty.city_id", &counterparty.city_id),
+                    (
+                        "recipient.counterparty.counterparty_id",
+                        &counterparty.counterparty_id,
+                    ),
+                    (
+                        "recipient.counterparty.contact_id",
+                        &counterparty.contact_id,
+                    ),
+                ] {
+                    if id.trim().is_empty() {
+                        errors.push(field, "is required");
+                    }
+                }
+            }
+            None => {}
+        }
+        if !is_phone(&self.phone) {
+            errors.push(
+                "recipient.phone",
+                "must be a phone number like 380991234567",
+            );
+        }
+        if let Err(address) = self.address.validate() {
+            for mut field in address.fields {
+                field.field = format!("recipient.{}", field.field);
+                errors.fields.push(field);
+            }
+        }
+        errors.into_result()
+    }
+
+    /// Uses a settlement found by `search_settlements`, so namesake villages in other
+    /// areas are not mixed up.
+    pub fn with_settlement(mut self, settlement: &NovaSettlementAddress) -> Self {
+        self.city_name = settlement.main_description.clone();
+        self.area = Some(settlement.area.clone()).filter(|a| !a.is_empty());
+        self.region = Some(settlement.region.clone()).filter(|r| !r.is_empty());
+        self.settlement_type =
+            Some(settlement.settlement_type_code.clone()).filter(|t| !t.is_empty());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: Result<(), ValidationError>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(e) => e.fields.into_iter().map(|f| f.field).collect(),
+        }
+    }
+
+    fn recipient(address: Address) -> Recipient {
+        Recipient::new(
+            "Київ".to_string(),
+            "Шевченко Тарас".to_string(),
+            "380671234567",
+            false,
+            address,
+        )
+    }
+
+    #[test]
+    fn addresses_need_every_part() {
+        assert!(fields(Address::warehouse(12).validate()).is_empty());
+        assert!(fields(Address::pochtomat_ref("postomat").validate()).is_empty());
+        assert_eq!(
+            fields(Address::warehouse_ref(" ").validate()),
+            ["address.warehouse"]
+        );
+        assert_eq!(
+            fields(Address::Postomat(WarehouseId::Number(String::new())).validate()),
+            ["address.postomat"]
+        );
+        assert_eq!(
+            fields(Address::street("", " ".to_string(), "5".to_string()).validate()),
+            ["address.street", "address.house"]
+        );
+        assert_eq!(
+            fields(Address::address(String::new(), "1".to_string(), String::new()).validate()),
+            ["address.street"]
+        );
+        assert!(fields(Address::saved_door("address").validate()).is_empty());
+        assert_eq!(fields(Address::saved_door(" ").validate()), ["address"]);
+    }
+
+    #[test]
+    fn private_person_lists_every_bad_field() {
+        assert!(fields(recipient(Address::warehouse(12)).validate()).is_empty());
+
+        let mut person = recipient(Address::street("", String::new(), String::new()))
+            .payer(NovaPayer::ThirdPerson(" ".to_string()));
+        person.city_name = String::new();
+        person.full_name = "Тарас".to_string();
+        person.phone = "067".to_string();
+        assert_eq!(
+            fields(person.validate()),
+            [
+                "recipient.city_name",
+                "recipient.full_name",
+                "recipient.payer",
+                "recipient.counterparty",
+                "recipient.phone",
+                "recipient.address.street",
+                "recipient.address.house",
+            ]
+        );
+
+        let cashless = recipient(Address::warehouse(12))
+            .payer(NovaPayer::Recipient)
+            .payment_method(NovaPaymentMethod::NonCash);
+        assert_eq!(fields(cashless.validate()), ["recipient.payment_method"]);
+    }
+
+    #[test]
+    fn organisation_lists_every_bad_field() {
+        let company = recipient(Address::warehouse(12))
+            .organization("12345678", "ownership", "Шевченко Тарас")
+            .payer(NovaPayer::Recipient)
+            .payment_method(NovaPaymentMethod::NonCash);
+        assert!(fields(company.validate()).is_empty());
+
+        let mut company = recipient(Address::street("street", "1".to_string(), String::new()))
+            .organization("1234", " ", "Тарас")
+            .counterparty("", "recipient", " ");
+        company.full_name = String::new();
+        assert_eq!(
+            fields(company.validate()),
+            [
+                "recipient.full_name",
+                "recipient.organization.edrpou",
+                "recipient.organization.ownership_form",
+                "recipient.organization.contact_name",
+                "recipient.counterparty.city_id",
+                "recipient.counterparty.contact_id",
+            ]
+        );
+    }
 }
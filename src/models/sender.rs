@@ -1,9 +1,28 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+use crate::error::ValidationError;
+
+/// Where the parcel leaves from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SenderAddress {
+    /// Ref of the warehouse we bring the parcel to.
+    Warehouse(String),
+    /// A courier picks the parcel up at this street address, a matching address saved
+    /// for the sender is reused and a new one is saved only when there is none.
+    Door {
+        street_ref: String,
+        house: String,
+        flat: Option<String>,
+    },
+    /// A courier picks the parcel up at an address saved with `door_address` or
+    /// `create_address`.
+    SavedDoor(String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SenderContact {
     pub city_id: String,
-    pub warehouse_id: String,
+    pub address: SenderAddress,
     pub contact_id: String,
     pub counterparty_id: String,
     pub contact_phone: String,
@@ -19,10 +38,89 @@ impl SenderContact {
     ) -> Self {
         SenderContact {
             city_id: city_ref.to_owned(),
-            warehouse_id: warehouse_ref.to_owned(),
+            address: SenderAddress::Warehouse(warehouse_ref.to_owned()),
             contact_id: contact_id.to_owned(),
             counterparty_id: counterparty_id.to_owned(),
             contact_phone: contact_phone.to_owned(),
         }
     }
+
+    /// Courier pickup from our own address instead of a warehouse drop-off, the
+    /// courier comes on the shipment date.
+    pub fn door(mut self, street_ref: &str, house: &str, flat: Option<&str>) -> Self {
+        self.address = SenderAddress::Door {
+            street_ref: street_ref.to_owned(),
+            house: house.to_owned(),
+            flat: flat.map(str::to_owned),
+        };
+        self
+    }
+
+    /// Courier pickup from an address already saved for the sender.
+    pub fn saved_door(mut self, address_ref: &str) -> Self {
+        self.address = SenderAddress::SavedDoor(address_ref.to_owned());
+        self
+    }
+
+    pub fn is_door(&self) -> bool {
+        !matches!(self.address, SenderAddress::Warehouse(_))
+    }
+
+    /// Collects every missing field of the pickup address.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors = ValidationError::default();
+        match &self.address {
+            SenderAddress::Warehouse(_) => {}
+            SenderAddress::Door {
+                street_ref, house, ..
+            } => {
+                if street_ref.trim().is_empty() {
+                    errors.push("sender.address.street_ref", "is required for door pickup");
+                }
+                if house.trim().is_empty() {
+                    errors.push("sender.address.house", "is required for door pickup");
+                }
+            }
+            SenderAddress::SavedDoor(address_ref) => {
+                if address_ref.trim().is_empty() {
+                    errors.push("sender.address", "ref is empty");
+                }
+            }
+        }
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender() -> SenderContact {
+        SenderContact::new("city", "warehouse", "contact", "sender", "380991234567")
+    }
+
+    fn fields(sender: &SenderContact) -> Vec<String> {
+        match sender.validate() {
+            Ok(()) => vec![],
+            Err(e) => e.fields.into_iter().map(|f| f.field).collect(),
+        }
+    }
+
+    #[test]
+    fn door_pickup_needs_street_and_house() {
+        assert!(fields(&sender()).is_empty());
+        assert!(fields(&sender().door("street", "1", None)).is_empty());
+        assert_eq!(
+            fields(&sender().door(" ", "", Some("5"))),
+            ["sender.address.street_ref", "sender.address.house"]
+        );
+    }
+
+    #[test]
+    fn saved_door_needs_a_ref() {
+        assert!(fields(&sender().saved_door("address")).is_empty());
+        assert_eq!(fields(&sender().saved_door(" ")), ["sender.address"]);
+        assert!(sender().saved_door("address").is_door());
+        assert!(!sender().is_door());
+    }
 }
@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::de;
+
+/// Page size used when walking every page of warehouses.
+pub const WAREHOUSES_PAGE_LIMIT: u32 = 500;
+
+/// Filter for [`crate::NovaPoshta::get_warehouses`], every field is optional.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct WarehouseQuery {
+    #[serde(rename = "Ref", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub find_by_string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settlement_ref: Option<String>,
+    /// Number of the warehouse in its city.
+    #[serde(rename = "WarehouseId", skip_serializing_if = "Option::is_none")]
+    pub warehouse_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_of_warehouse_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "flag")]
+    pub bicycle_parking: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "flag")]
+    pub post_finance: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+fn flag<S: Serializer>(value: &Option<bool>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if value.unwrap_or_default() { "1" } else { "0" })
+}
+
+impl WarehouseQuery {
+    // Query like Львів, 100
+    pub fn find(query: &str) -> Self {
+        WarehouseQuery {
+            find_by_string: Some(query.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    pub fn id(warehouse_ref: &str) -> Self {
+        WarehouseQuery {
+            id: Some(warehouse_ref.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    pub fn city_ref(mut self, city_ref: &str) -> Self {
+        self.city_ref = Some(city_ref.to_owned());
+        self
+    }
+
+    pub fn city_name(mut self, city_name: &str) -> Self {
+        self.city_name = Some(city_name.to_owned());
+        self
+    }
+
+    pub fn settlement_ref(mut self, settlement_ref: &str) -> Self {
+        self.settlement_ref = Some(settlement_ref.to_owned());
+        self
+    }
+
+    pub fn number(mut self, number: &str) -> Self {
+        self.warehouse_number = Some(number.to_owned());
+        self
+    }
+
+    pub fn type_of_warehouse(mut self, type_ref: &str) -> Self {
+        self.type_of_warehouse_ref = Some(type_ref.to_owned());
+        self
+    }
+
+    pub fn bicycle_parking(mut self, required: bool) -> Self {
+        self.bicycle_parking = Some(required);
+        self
+    }
+
+    pub fn post_finance(mut self, required: bool) -> Self {
+        self.post_finance = Some(required);
+        self
+    }
+
+    /// Pages start at 1.
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl From<String> for WarehouseQuery {
+    fn from(query: String) -> Self {
+        WarehouseQuery {
+            find_by_string: Some(query),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&str> for WarehouseQuery {
+    fn from(query: &str) -> Self {
+        WarehouseQuery::find(query)
+    }
+}
+
+/// Opening hours of one day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpeningHours {
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+}
+
+impl OpeningHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        self.open <= time && time < self.close
+    }
+
+    // Parses 08:00-20:00
+    fn parse(value: &str) -> Option<Self> {
+        let (open, close) = value.trim().split_once('-')?;
+        let time = |t: &str| match t.trim() {
+            "24:00" => NaiveTime::from_hms_opt(23, 59, 59),
+            t => NaiveTime::parse_from_str(t, "%H:%M").ok(),
+        };
+        Some(OpeningHours {
+            open: time(open)?,
+            close: time(close)?,
+        })
+    }
+}
+
+/// Weekly schedule, days without hours are days off.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NovaSchedule {
+    pub days: HashMap<Weekday, OpeningHours>,
+}
+
+impl NovaSchedule {
+    pub fn hours(&self, day: Weekday) -> Option<&OpeningHours> {
+        self.days.get(&day)
+    }
+
+    pub fn is_open_at(&self, at: NaiveDateTime) -> bool {
+        self.hours(at.weekday())
+            .is_some_and(|hours| hours.contains(at.time()))
+    }
+}
+
+impl<'de> Deserialize<'de> for NovaSchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Warehouses without a schedule send an empty array instead of an object.
+        let raw = match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Object(map) => map,
+            _ => Default::default(),
+        };
+        let days = raw
+            .into_iter()
+            .filter_map(|(day, hours)| {
+                Some((day.parse().ok()?, OpeningHours::parse(hours.as_str()?)?))
+            })
+            .collect();
+        Ok(NovaSchedule { days })
+    }
+}
+
+/// Largest parcel a warehouse accepts, in centimeters; zero means no limit.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct NovaDimensionsLimit {
+    #[serde(deserialize_with = "de::number")]
+    pub width: f64,
+    #[serde(deserialize_with = "de::number")]
+    pub height: f64,
+    #[serde(deserialize_with = "de::number")]
+    pub length: f64,
+}
+
+impl NovaDimensionsLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.width <= 0.0 && self.height <= 0.0 && self.length <= 0.0
+    }
+
+    /// Whether a box fits in any orientation.
+    pub fn fits(&self, width: f64, height: f64, length: f64) -> bool {
+        if self.is_unlimited() {
+            return true;
+        }
+        let mut limit = [self.width, self.height, self.length];
+        let mut size = [width, height, length];
+        limit.sort_by(f64::total_cmp);
+        size.sort_by(f64::total_cmp);
+        limit.iter().zip(size.iter()).all(|(l, s)| s <= l)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NovaWarehouseStatus {
+    Working,
+    NotWorking,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+pub const TYPE_BRANCH_REF: &str = "841339c7-591a-42e2-8233-7a0a00f0ed6f";
+pub const TYPE_CARGO_BRANCH_REF: &str = "9a68df70-0267-42a8-bb5c-37f427e36ee4";
+pub const TYPE_POSTOMAT_REF: &str = "f9316480-5f2d-425d-bc2c-ac7cd29decf0";
+pub const TYPE_PRIVATBANK_POSTOMAT_REF: &str = "95dc212d-479c-4ffb-a8ab-8c1b9073d0bc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NovaWarehouseKind {
+    Branch,
+    CargoBranch,
+    Postomat,
+    Other,
+}
+
+impl NovaWarehouseKind {
+    /// Kind from `TypeOfWarehouse`, falling back to `CategoryOfWarehouse`.
+    pub fn from_refs(type_of_warehouse_ref: &str, category: &str) -> Self {
+        match type_of_warehouse_ref {
+            TYPE_BRANCH_REF => NovaWarehouseKind::Branch,
+            TYPE_CARGO_BRANCH_REF => NovaWarehouseKind::CargoBranch,
+            TYPE_POSTOMAT_REF | TYPE_PRIVATBANK_POSTOMAT_REF => NovaWarehouseKind::Postomat,
+            _ => match category {
+                "Branch" => NovaWarehouseKind::Branch,
+                "Postomat" => NovaWarehouseKind::Postomat,
+                _ => NovaWarehouseKind::Other,
+            },
+        }
+    }
+}
+
+/// Largest postomat cell, used when a postomat does not report its own limits.
+pub const POSTOMAT_MAX_WEIGHT: f64 = 20.0;
+pub const POSTOMAT_MAX_DIMENSIONS: NovaDimensionsLimit = NovaDimensionsLimit {
+    width: 40.0,
+    height: 30.0,
+    length: 60.0,
+};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance between two points in kilometers.
+pub fn distance_km(lat0: f64, lon0: f64, lat1: f64, lon1: f64) -> f64 {
+    let (lat0, lat1) = (lat0.to_radians(), lat1.to_radians());
+    let d_lat = lat1 - lat0;
+    let d_lon = (lon1 - lon0).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat0.cos() * lat1.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Conditions for [`nearest_warehouses`], an empty filter accepts every warehouse that
+/// is working and open for selection.
+#[derive(Debug, Clone, Default)]
+pub struct WarehouseFilter {
+    /// Accepted kinds, empty accepts all of them.
+    pub kinds: Vec<NovaWarehouseKind>,
+    /// Weight of the heaviest seat in kilograms that must be accepted.
+    pub min_place_weight: Option<f64>,
+    /// Only warehouses receiving parcels at this local time.
+    pub open_at: Option<NaiveDateTime>,
+}
+
+impl WarehouseFilter {
+    pub fn kind(mut self, kind: NovaWarehouseKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    pub fn accepts_weight(mut self, kilograms: f64) -> Self {
+        self.min_place_weight = Some(kilograms);
+        self
+    }
+
+    pub fn open_at(mut self, at: NaiveDateTime) -> Self {
+        self.open_at = Some(at);
+        self
+    }
+
+    /// Warehouses marked `DenyToSelect` or `NotWorking` never match.
+    pub fn matches(&self, warehouse: &crate::NovaWarehouse) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&warehouse.kind()) {
+            return false;
+        }
+        if let Some(weight) = self.min_place_weight {
+            let limit = warehouse.place_max_weight_allowed;
+            if limit > 0.0 && limit < weight {
+                return false;
+            }
+        }
+        if let Some(at) = self.open_at {
+            let schedule = if warehouse.reception.days.is_empty() {
+                &warehouse.schedule
+            } else {
+                &warehouse.reception
+            };
+            if !schedule.is_open_at(at) {
+                return false;
+            }
+        }
+        !warehouse.deny_to_select && warehouse.warehouse_status != NovaWarehouseStatus::NotWorking
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NearbyWarehouse {
+    pub warehouse: crate::NovaWarehouse,
+    pub distance_km: f64,
+}
+
+/// Warehouses within `radius_km` of a point that pass `filter`, closest first.
+///
+/// Works on any list of warehouses, e.g. a cached directory.
+pub fn nearest_warehouses<'a>(
+    warehouses: impl IntoIterator<Item = &'a crate::NovaWarehouse>,
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+    filter: &WarehouseFilter,
+) -> Vec<NearbyWarehouse> {
+    let mut nearby: Vec<NearbyWarehouse> = warehouses
+        .into_iter()
+        // Zero coordinates mean the api does not know where the warehouse is.
+        .filter(|w| w.latitude != 0.0 || w.longitude != 0.0)
+        .map(|w| {
+            let distance = distance_km(latitude, longitude, w.latitude, w.longitude);
+            (w, distance)
+        })
+        .filter(|(w, distance)| *distance <= radius_km && filter.matches(w))
+        .map(|(w, distance)| NearbyWarehouse {
+            warehouse: w.clone(),
+            distance_km: distance,
+        })
+        .collect();
+    nearby.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+    nearby
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NovaWarehouse;
+    use chrono::NaiveDate;
+
+    /// Trimmed `getWarehouses` item of a postomat, as the api sends it.
+    const POSTOMAT: &str = r#"{
+        "SiteKey": "21722",
+        "Description": "Поштомат \"Нова Пошта\" №5590: вул. Хрещатик, 22",
+        "DescriptionRu": "Почтомат \"Новая Почта\" №5590: ул. Крещатик, 22",
+        "ShortAddress": "Київ, Хрещатик, 22",
+        "Phone": "",
+        "TypeOfWarehouse": "f9316480-5f2d-425d-bc2c-ac7cd29decf0",
+        "Ref": "0c3b5e79-63e6-11ea-8513-b88303659df5",
+        "Number": "5590",
+        "CityRef": "8d5a980d-391c-11dd-90d9-001a92567626",
+        "CityDescription": "Київ",
+        "SettlementRef": "e718a680-4b33-11e4-ab6d-005056801329",
+        "Longitude": "30.521846000000000",
+        "Latitude": 50.447450,
+        "PostFinance": "0",
+        "BicycleParking": "1",
+        "POSTerminal": "1",
+        "PlaceMaxWeightAllowed": "",
+        "TotalMaxWeightAllowed": 0,
+        "SendingLimitationsOnDimensions": [],
+        "ReceivingLimitationsOnDimensions": {"Width": 40, "Height": "30", "Length": "60.5"},
+        "Reception": [],
+        "Delivery": {"Monday": "-", "Tuesday": "09:00-18:00"},
+        "Schedule": {
+            "Monday": "00:00-24:00",
+            "Saturday": "10:00-16:00",
+            "Sunday": "-"
+        },
+        "WarehouseStatus": "Working",
+        "CategoryOfWarehouse": "Postomat",
+        "WarehouseIndex": "11/22",
+        "DenyToSelect": "0"
+    }"#;
+
+    #[test]
+    fn decodes_loose_warehouse_item() {
+        let w: NovaWarehouse = serde_json::from_str(POSTOMAT).unwrap();
+        assert_eq!(w.number, "5590");
+        assert_eq!(w.kind(), NovaWarehouseKind::Postomat);
+        assert_eq!(w.longitude, 30.521846);
+        assert_eq!(w.latitude, 50.44745);
+        assert_eq!(w.place_max_weight_allowed, 0.0);
+        assert_eq!(w.total_max_weight_allowed, 0.0);
+        assert!(!w.post_finance);
+        assert!(w.bicycle_parking);
+        assert!(!w.deny_to_select);
+        assert_eq!(w.warehouse_status, NovaWarehouseStatus::Working);
+
+        assert!(w.sending_limitations_on_dimensions.is_unlimited());
+        assert_eq!(
+            w.receiving_limitations_on_dimensions,
+            NovaDimensionsLimit {
+                width: 40.0,
+                height: 30.0,
+                length: 60.5,
+            }
+        );
+
+        assert!(w.reception.days.is_empty());
+        assert_eq!(w.delivery.days.len(), 1);
+        let monday = w.schedule.hours(Weekday::Mon).unwrap();
+        assert_eq!(monday.open, NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        assert_eq!(monday.close, NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+        assert!(w.schedule.hours(Weekday::Sun).is_none());
+        let saturday = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert!(w
+            .schedule
+            .is_open_at(saturday.and_hms_opt(15, 59, 0).unwrap()));
+        assert!(!w
+            .schedule
+            .is_open_at(saturday.and_hms_opt(16, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn rejects_garbage_numbers() {
+        let item = POSTOMAT.replace("\"30.521846000000000\"", "\"east\"");
+        assert!(serde_json::from_str::<NovaWarehouse>(&item).is_err());
+    }
+
+    fn warehouse(id: &str, latitude: f64, longitude: f64) -> NovaWarehouse {
+        let mut item: serde_json::Value = serde_json::from_str(POSTOMAT).unwrap();
+        item["Ref"] = id.into();
+        item["Latitude"] = latitude.into();
+        item["Longitude"] = longitude.into();
+        serde_json::from_value(item).unwrap()
+    }
+
+    fn branch(id: &str, latitude: f64, longitude: f64) -> NovaWarehouse {
+        let mut w = warehouse(id, latitude, longitude);
+        w.type_of_warehouse_ref = TYPE_BRANCH_REF.to_string();
+        w.category_of_warehouse = "Branch".to_string();
+        w
+    }
+
+    #[test]
+    fn distance_between_cities() {
+        let kyiv_lviv = distance_km(50.4501, 30.5234, 49.8397, 24.0297);
+        assert!((kyiv_lviv - 469.0).abs() < 2.0, "{}", kyiv_lviv);
+        assert_eq!(distance_km(50.0, 30.0, 50.0, 30.0), 0.0);
+        assert_eq!(
+            distance_km(50.0, 30.0, 49.0, 24.0),
+            distance_km(49.0, 24.0, 50.0, 30.0)
+        );
+    }
+
+    #[test]
+    fn filter_checks_kind_weight_and_hours() {
+        let postomat = warehouse("postomat", 50.0, 30.0);
+        let mut heavy = branch("branch", 50.0, 30.0);
+        heavy.place_max_weight_allowed = 30.0;
+
+        assert!(WarehouseFilter::default().matches(&postomat));
+        let branches = WarehouseFilter::default().kind(NovaWarehouseKind::Branch);
+        assert!(!branches.matches(&postomat));
+        assert!(branches.matches(&heavy));
+        let either = branches.kind(NovaWarehouseKind::Postomat);
+        assert!(either.matches(&postomat));
+
+        let mut light = heavy.clone();
+        light.place_max_weight_allowed = 10.0;
+        let weight = WarehouseFilter::default().accepts_weight(15.0);
+        assert!(weight.matches(&heavy));
+        assert!(!weight.matches(&light));
+        // Zero is an unknown limit, not a zero one.
+        assert!(weight.matches(&postomat));
+
+        let saturday = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let evening = WarehouseFilter::default().open_at(saturday.and_hms_opt(18, 0, 0).unwrap());
+        let noon = WarehouseFilter::default().open_at(saturday.and_hms_opt(12, 0, 0).unwrap());
+        assert!(!evening.matches(&postomat));
+        assert!(noon.matches(&postomat));
+        // Reception hours win over the general schedule when the api sends them.
+        let mut receiving = postomat.clone();
+        receiving.reception = serde_json::from_str(r#"{"Saturday": "17:00-20:00"}"#).unwrap();
+        assert!(evening.matches(&receiving));
+        assert!(!noon.matches(&receiving));
+    }
+
+    #[test]
+    fn closed_and_hidden_warehouses_never_match() {
+        let mut hidden = warehouse("hidden", 50.0, 30.0);
+        hidden.deny_to_select = true;
+        let mut closed = warehouse("closed", 50.0, 30.0);
+        closed.warehouse_status = NovaWarehouseStatus::NotWorking;
+        let mut unknown = warehouse("unknown", 50.0, 30.0);
+        unknown.warehouse_status = NovaWarehouseStatus::Unknown;
+
+        let filter = WarehouseFilter::default();
+        assert!(!filter.matches(&hidden));
+        assert!(!filter.matches(&closed));
+        assert!(filter.matches(&unknown));
+    }
+
+    #[test]
+    fn nearest_sorts_and_cuts_off() {
+        let warehouses = [
+            warehouse("far", 50.10, 30.0),
+            warehouse("near", 50.01, 30.0),
+            warehouse("nowhere", 0.0, 0.0),
+            branch("middle", 50.05, 30.0),
+            warehouse("away", 51.0, 30.0),
+        ];
+        let ids = |found: Vec<NearbyWarehouse>| -> Vec<String> {
+            found.into_iter().map(|n| n.warehouse.id).collect()
+        };
+
+        let found = nearest_warehouses(&warehouses, 50.0, 30.0, 20.0, &Default::default());
+        assert!(
+            (found[0].distance_km - 1.11).abs() < 0.01,
+            "{}",
+            found[0].distance_km
+        );
+        assert_eq!(ids(found), ["near", "middle", "far"]);
+
+        let postomats = WarehouseFilter::default().kind(NovaWarehouseKind::Postomat);
+        assert_eq!(
+            ids(nearest_warehouses(
+                &warehouses,
+                50.0,
+                30.0,
+                20.0,
+                &postomats
+            )),
+            ["near", "far"]
+        );
+        assert_eq!(
+            ids(nearest_warehouses(
+                &warehouses,
+                50.0,
+                30.0,
+                0.5,
+                &Default::default()
+            )),
+            Vec::<String>::new()
+        );
+        // A point at 0,0 still skips warehouses without coordinates.
+        assert!(nearest_warehouses(&warehouses, 0.0, 0.0, 1.0, &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn box_fits_in_any_orientation() {
+        let limit = NovaDimensionsLimit {
+            width: 40.0,
+            height: 30.0,
+            length: 60.0,
+        };
+        assert!(limit.fits(60.0, 40.0, 30.0));
+        assert!(limit.fits(25.0, 59.0, 39.0));
+        assert!(!limit.fits(61.0, 10.0, 10.0));
+        assert!(!limit.fits(35.0, 35.0, 35.0));
+        assert!(NovaDimensionsLimit::default().fits(500.0, 500.0, 500.0));
+    }
+
+    #[test]
+    fn query_sends_flags_as_digits() {
+        let query = WarehouseQuery::find("Київ")
+            .number("12")
+            .type_of_warehouse("postomat")
+            .bicycle_parking(true)
+            .post_finance(false)
+            .page(2)
+            .limit(50);
+        assert_eq!(
+            serde_json::to_value(query).unwrap(),
+            serde_json::json!({
+                "FindByString": "Київ",
+                "WarehouseId": "12",
+                "TypeOfWarehouseRef": "postomat",
+                "BicycleParking": "1",
+                "PostFinance": "0",
+                "Page": 2,
+                "Limit": 50
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(WarehouseQuery::id("warehouse")).unwrap(),
+            serde_json::json!({ "Ref": "warehouse" })
+        );
+    }
+}
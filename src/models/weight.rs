@@ -0,0 +1,174 @@
+use super::cargo::{Cargo, NovaOptionsSeat};
+
+/// Cubic centimeters per kilogram of volumetric weight.
+pub const VOLUMETRIC_DIVISOR: f64 = 4000.0;
+
+/// Heaviest seat still sent as a parcel.
+pub const PARCEL_MAX_WEIGHT: f64 = 30.0;
+/// Longest side of a seat still sent as a parcel, in centimeters.
+pub const PARCEL_MAX_SIDE: f64 = 120.0;
+/// Heaviest seat accepted as cargo at a cargo branch.
+pub const CARGO_MAX_WEIGHT: f64 = 1000.0;
+/// Longest side of a seat accepted as cargo, in centimeters.
+pub const CARGO_MAX_SIDE: f64 = 300.0;
+
+/// Which limits a seat stays within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SeatCategory {
+    Parcel,
+    /// Over the parcel limits, has to go as cargo.
+    Cargo,
+    /// Over the cargo limits too, Nova Poshta will not take it as is.
+    Oversized,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeatWeight {
+    pub actual: f64,
+    /// Cubic meters.
+    pub volume: f64,
+    pub volumetric: f64,
+    /// The larger of actual and volumetric weight, what the price is based on.
+    pub billable: f64,
+    pub category: SeatCategory,
+}
+
+impl NovaOptionsSeat {
+    /// Seat with the volume derived from its dimensions.
+    pub fn from_dimensions(width: i32, length: i32, height: i32, weight: f32) -> Self {
+        NovaOptionsSeat::new(0.0, width, length, height, weight).with_computed_volume()
+    }
+
+    /// Replaces the supplied volume with the one derived from the dimensions.
+    pub fn with_computed_volume(mut self) -> Self {
+        self.Volume = self.volume_m3() as f32;
+        self
+    }
+
+    fn cubic_cm(&self) -> f64 {
+        self.Width as f64 * self.Length as f64 * self.Height as f64
+    }
+
+    pub fn volume_m3(&self) -> f64 {
+        self.cubic_cm() / 1_000_000.0
+    }
+
+    pub fn volumetric_weight(&self) -> f64 {
+        self.cubic_cm() / VOLUMETRIC_DIVISOR
+    }
+
+    pub fn billable_weight(&self) -> f64 {
+        self.volumetric_weight().max(self.weight as f64)
+    }
+
+    pub fn category(&self) -> SeatCategory {
+        let side = self.Width.max(self.Length).max(self.Height) as f64;
+        let weight = self.weight as f64;
+        if weight <= PARCEL_MAX_WEIGHT && side <= PARCEL_MAX_SIDE {
+            SeatCategory::Parcel
+        } else if weight <= CARGO_MAX_WEIGHT && side <= CARGO_MAX_SIDE {
+            SeatCategory::Cargo
+        } else {
+            SeatCategory::Oversized
+        }
+    }
+
+    pub fn seat_weight(&self) -> SeatWeight {
+        SeatWeight {
+            actual: self.weight as f64,
+            volume: self.volume_m3(),
+            volumetric: self.volumetric_weight(),
+            billable: self.billable_weight(),
+            category: self.category(),
+        }
+    }
+}
+
+/// Weights of every seat of a shipment and their totals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShipmentWeight {
+    pub seats: Vec<SeatWeight>,
+    pub actual: f64,
+    pub volume: f64,
+    pub volumetric: f64,
+    /// Sum of per-seat billable weights, every seat is priced on its own.
+    pub billable: f64,
+}
+
+impl ShipmentWeight {
+    pub fn new(cargos: &[Cargo]) -> Self {
+        let seats: Vec<SeatWeight> = cargos
+            .iter()
+            .map(|c| c.options_seat.seat_weight())
+            .collect();
+        ShipmentWeight {
+            actual: seats.iter().map(|s| s.actual).sum(),
+            volume: seats.iter().map(|s| s.volume).sum(),
+            volumetric: seats.iter().map(|s| s.volumetric).sum(),
+            billable: seats.iter().map(|s| s.billable).sum(),
+            seats,
+        }
+    }
+
+    /// The most demanding category among the seats.
+    pub fn category(&self) -> SeatCategory {
+        self.seats
+            .iter()
+            .map(|s| s.category)
+            .max()
+            .unwrap_or(SeatCategory::Parcel)
+    }
+
+    /// Indexes of seats that do not fit into `category`.
+    pub fn exceeding(&self, category: SeatCategory) -> Vec<usize> {
+        self.seats
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.category > category)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cargo(width: i32, length: i32, height: i32, weight: f32) -> Cargo {
+        Cargo::new(
+            100,
+            NovaOptionsSeat::from_dimensions(width, length, height, weight),
+            false,
+            "box".to_string(),
+        )
+    }
+
+    #[test]
+    fn volume_follows_dimensions() {
+        let seat = NovaOptionsSeat::new(5.0, 40, 30, 20, 2.0).with_computed_volume();
+        assert!((seat.Volume - 0.024).abs() < 1e-6);
+        assert_eq!(seat.volumetric_weight(), 6.0);
+        assert_eq!(seat.billable_weight(), 6.0);
+    }
+
+    #[test]
+    fn heavy_small_seat_is_billed_by_actual_weight() {
+        let seat = NovaOptionsSeat::from_dimensions(10, 10, 10, 3.0);
+        assert_eq!(seat.volumetric_weight(), 0.25);
+        assert_eq!(seat.billable_weight(), 3.0);
+    }
+
+    #[test]
+    fn shipment_sums_seats_and_flags_limits() {
+        let weight = ShipmentWeight::new(&[
+            cargo(40, 30, 20, 2.0),
+            cargo(150, 60, 60, 40.0),
+            cargo(20, 20, 20, 1200.0),
+        ]);
+        assert_eq!(weight.actual, 1242.0);
+        assert_eq!(weight.billable, 6.0 + 135.0 + 1200.0);
+        assert_eq!(weight.category(), SeatCategory::Oversized);
+        assert_eq!(weight.exceeding(SeatCategory::Parcel), vec![1, 2]);
+        assert_eq!(weight.exceeding(SeatCategory::Cargo), vec![2]);
+    }
+}
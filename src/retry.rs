@@ -0,0 +1,243 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::error::NovaError;
+
+/// Exponential backoff applied to failed requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Sleep a random duration between zero and the backoff instead of the full backoff.
+    pub jitter: bool,
+    /// Api `errorCodes` that mean the key is throttled and the call can be repeated.
+    ///
+    /// The api reports throttling as `success: false` with HTTP 200, errors whose
+    /// message reads "too many requests" are retried even when their code is not listed.
+    pub throttle_codes: &'static [u64],
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            throttle_codes: &[],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Every request is sent exactly once.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the attempt following `attempt` (counted from 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        if !self.jitter {
+            return backoff;
+        }
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(attempt);
+        let fraction = (hasher.finish() % 1_000) as f64 / 1_000.0;
+        backoff.mul_f64(fraction)
+    }
+}
+
+/// Which failures of a single call are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Retry {
+    /// `Transient` for read methods, `ConnectOnly` for everything that changes data.
+    #[default]
+    Auto,
+    /// Connection errors, timeouts, 429/5xx statuses and throttling api errors.
+    Transient,
+    /// Only failures where the request never reached the server.
+    ConnectOnly,
+    Never,
+}
+
+impl Retry {
+    pub(crate) fn resolve(self, model: &str, method: &str) -> Retry {
+        match self {
+            Retry::Auto if is_idempotent(model, method) => Retry::Transient,
+            Retry::Auto => Retry::ConnectOnly,
+            other => other,
+        }
+    }
+
+    pub(crate) fn should_retry(self, error: &NovaError, policy: &RetryPolicy) -> bool {
+        match self {
+            Retry::Auto | Retry::Transient => {
+                error.is_transient() || error.is_throttled(policy.throttle_codes)
+            }
+            Retry::ConnectOnly => error.is_connect(),
+            Retry::Never => false,
+        }
+    }
+}
+
+/// Wording of the api's throttling errors, "To many requests" is its own spelling.
+const THROTTLE_MESSAGES: [&str; 2] = ["too many requests", "to many requests"];
+
+/// Reference books and lookups can be repeated safely, `save`/`update`/`delete` can not.
+fn is_idempotent(model: &str, method: &str) -> bool {
+    matches!(model, "TrackingDocument" | "Common" | "CommonGeneral")
+        || method.starts_with("get")
+        || method.starts_with("search")
+}
+
+impl NovaError {
+    /// The request never reached the server and is safe to send again.
+    pub fn is_connect(&self) -> bool {
+        matches!(self, NovaError::Transport(e) if e.is_connect())
+    }
+
+    /// The request failed on the way or the server was overloaded.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NovaError::Transport(e) => {
+                e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+            }
+            NovaError::Status { status, .. } => status.is_server_error() || status.as_u16() == 429,
+            _ => false,
+        }
+    }
+
+    /// The api refused the call with one of the throttling `codes` or messages.
+    pub fn is_throttled(&self, codes: &[u64]) -> bool {
+        let NovaError::Api(e) = self else {
+            return false;
+        };
+        e.error_codes.iter().any(|c| codes.contains(c))
+            || e.errors.iter().any(|message| {
+                let message = message.to_lowercase();
+                THROTTLE_MESSAGES.iter().any(|m| message.contains(m))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{NovaApiError, ValidationError};
+    use reqwest::StatusCode;
+
+    fn status(code: u16) -> NovaError {
+        NovaError::Status {
+            status: StatusCode::from_u16(code).unwrap(),
+            body: String::new(),
+        }
+    }
+
+    fn api(message: &str, codes: &[u64]) -> NovaError {
+        NovaApiError {
+            errors: vec![message.to_string()],
+            error_codes: codes.to_vec(),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn delay_grows_and_caps() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn jitter_does_not_overflow_huge_delays() {
+        let policy = RetryPolicy {
+            max_delay: Duration::MAX,
+            base_delay: Duration::from_secs(u64::MAX / 4),
+            ..Default::default()
+        };
+        for attempt in 1..40 {
+            assert!(policy.delay(attempt) <= Duration::MAX);
+        }
+        let policy = RetryPolicy::default();
+        assert!(policy.delay(2) <= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn reads_are_retried_and_writes_are_not() {
+        assert!(is_idempotent("Address", "getCities"));
+        assert!(is_idempotent("Address", "searchSettlements"));
+        assert!(is_idempotent("TrackingDocument", "getStatusDocuments"));
+        assert!(is_idempotent("Common", "getCargoTypes"));
+        assert!(!is_idempotent("InternetDocument", "save"));
+        assert!(!is_idempotent("ContactPerson", "delete"));
+
+        assert_eq!(
+            Retry::Auto.resolve("Address", "getCities"),
+            Retry::Transient
+        );
+        assert_eq!(
+            Retry::Auto.resolve("InternetDocument", "save"),
+            Retry::ConnectOnly
+        );
+        assert_eq!(Retry::Never.resolve("Address", "getCities"), Retry::Never);
+        assert_eq!(
+            Retry::Transient.resolve("InternetDocument", "save"),
+            Retry::Transient
+        );
+    }
+
+    #[test]
+    fn transient_failures_are_retried() {
+        let policy = RetryPolicy {
+            throttle_codes: &[42],
+            ..Default::default()
+        };
+        for code in [500, 502, 503, 429] {
+            assert!(
+                Retry::Transient.should_retry(&status(code), &policy),
+                "{}",
+                code
+            );
+        }
+        assert!(!Retry::Transient.should_retry(&status(400), &policy));
+        assert!(Retry::Transient.should_retry(&api("Limit reached", &[42]), &policy));
+        assert!(!Retry::Transient.should_retry(&api("Limit reached", &[7]), &policy));
+        assert!(
+            !Retry::Transient.should_retry(&api("Limit reached", &[42]), &RetryPolicy::default())
+        );
+        assert!(
+            Retry::Transient.should_retry(&api("To many requests", &[]), &RetryPolicy::default())
+        );
+        assert!(Retry::Transient.should_retry(
+            &api("Too Many Requests, try later", &[7]),
+            &RetryPolicy::default()
+        ));
+        assert!(!Retry::Transient
+            .should_retry(&api("Document not found", &[]), &RetryPolicy::default()));
+        assert!(
+            !Retry::Transient.should_retry(&ValidationError::new("field", "bad").into(), &policy)
+        );
+
+        assert!(!Retry::ConnectOnly.should_retry(&status(503), &policy));
+        assert!(!Retry::ConnectOnly.should_retry(&api("To many requests", &[42]), &policy));
+        assert!(!Retry::Never.should_retry(&status(503), &policy));
+    }
+}
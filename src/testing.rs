@@ -0,0 +1,116 @@
+//! Stub api server for tests that check what the client sends.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::NovaPoshta;
+
+/// Every request body received, in order.
+#[derive(Clone, Default)]
+pub(crate) struct Requests(Arc<Mutex<Vec<Value>>>);
+
+impl Requests {
+    pub(crate) fn all(&self) -> Vec<Value> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// `methodProperties` of the calls to `model.method`.
+    pub(crate) fn calls(&self, model: &str, method: &str) -> Vec<Value> {
+        self.all()
+            .into_iter()
+            .filter(|r| r["modelName"] == model && r["calledMethod"] == method)
+            .map(|r| r["methodProperties"].clone())
+            .collect()
+    }
+}
+
+async fn read_body(socket: &mut TcpStream) -> Value {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length: usize = text[..end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().unwrap())
+                })
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                return serde_json::from_slice(&request[end + 4..end + 4 + length]).unwrap();
+            }
+        }
+        assert!(n > 0, "connection closed before the body arrived");
+    }
+}
+
+/// Records each request and answers with the status and body `handler` resolves to.
+pub(crate) async fn serve<F, Fut>(handler: F) -> (String, Requests)
+where
+    F: Fn(Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = (u16, Value)> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let recorded = requests.clone();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let requests = requests.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let request = read_body(&mut socket).await;
+                requests.0.lock().unwrap().push(request.clone());
+                let (status, body) = handler(request).await;
+                let body = body.to_string();
+                let status = StatusCode::from_u16(status).unwrap();
+                let response = format!(
+                    "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status.as_u16(),
+                    status.canonical_reason().unwrap_or(""),
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    (url, recorded)
+}
+
+/// Successful envelope around `data`.
+pub(crate) fn success(data: Value) -> Value {
+    json!({
+        "success": true,
+        "data": data,
+        "errors": [],
+        "warnings": []
+    })
+}
+
+/// Client talking to a server that answers with `respond(request)`, status and body.
+pub(crate) async fn mock_responses(
+    respond: impl Fn(&Value) -> (u16, Value) + Send + Sync + 'static,
+) -> (NovaPoshta, Requests) {
+    let (url, requests) = serve(move |request| std::future::ready(respond(&request))).await;
+    let nova = NovaPoshta::builder("key").base_url(url).build().unwrap();
+    (nova, requests)
+}
+
+/// Client talking to a server that wraps `data(request)` in a successful envelope.
+pub(crate) async fn mock_api(
+    data: impl Fn(&Value) -> Value + Send + Sync + 'static,
+) -> (NovaPoshta, Requests) {
+    mock_responses(move |request| (200, success(data(request)))).await
+}